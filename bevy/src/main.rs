@@ -1,3 +1,4 @@
+use bevy::asset::io::file::FileAssetReader;
 use bevy::asset::AssetPlugin;
 use bevy::audio::{AudioPlayer, PlaybackSettings, Volume};
 use bevy::camera::visibility::RenderLayers;
@@ -11,7 +12,16 @@ use bevy::asset::RenderAssetUsages;
 use bevy::ui::IsDefaultUiCamera;
 use bevy::text::LineHeight;
 use bevy::ecs::query::QueryFilter;
+use bevy::ecs::system::SystemParam;
+use bevy::input::gamepad::GamepadConnectionEvent;
+use bevy::input::InputSystems;
+use bevy::window::{MonitorSelection, PrimaryWindow, WindowMode};
 use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 const LEVEL_WIDTH: f32 = 1600.0;
 const LEVEL_HEIGHT: f32 = 225.0;
@@ -19,6 +29,32 @@ const VIEW_WIDTH: f32 = 400.0;
 const VIEW_HEIGHT: f32 = 225.0;
 
 const DOOR_OPEN_OFFSET: f32 = 26.0;
+const BLOCK_GRAVITY: f32 = -520.0;
+const BLOCK_MAX_FALL_SPEED: f32 = 320.0;
+const GATE_SPEED: f32 = 60.0;
+const DUEL_RANGE: f32 = 64.0;
+const DUEL_BREAK_RANGE: f32 = 96.0;
+const DUEL_FLOOR_TOLERANCE: f32 = 16.0;
+const GUARD_STRIKE_REACH: f32 = 30.0;
+const GUARD_SIGHT_RANGE: f32 = 140.0;
+const GUARD_VIEW_CONE_COS: f32 = 0.77;
+const GUARD_NOTICE_RATE: f32 = 1.6;
+const GUARD_AWARENESS_DECAY: f32 = 0.35;
+const GUARD_HEARING_HEIGHT: f32 = 60.0;
+const AMBIENT_LIGHT: f32 = 0.3;
+const TORCH_LIGHT_RADIUS: f32 = 90.0;
+const CROUCH_VISIBILITY: f32 = 0.5;
+const JUMP_NOISE_RADIUS: f32 = 50.0;
+const LAND_NOISE_RADIUS: f32 = 40.0;
+const ARROW_LIFETIME: f32 = 2.5;
+const PROJECTILE_GRAVITY: f32 = -520.0;
+const DAGGER_SPEED: f32 = 200.0;
+const MAX_DAGGERS: u32 = 3;
+const PLAYER_MAX_HEALTH: u32 = 5;
+const BOSS_PHASE_STAGGER: f32 = 0.8;
+const GUARD_CHASE_SCALE: f32 = 1.75;
+const GUARD_ALERT_TIME: f32 = 0.4;
+const GUARD_GIVE_UP_TIME: f32 = 2.0;
 
 const Z_BG: f32 = -20.0;
 const Z_WALL: f32 = -15.0;
@@ -27,8 +63,33 @@ const Z_PLATFORM: f32 = 0.0;
 const Z_INTERACT: f32 = 1.0;
 const Z_ACTOR: f32 = 5.0;
 const Z_GLOW: f32 = 2.0;
+const Z_WATER: f32 = 6.0;
 const Z_LABEL: f32 = 30.0;
 
+const ASSET_ROOT: &str = "..";
+const MOVEMENT_TUNING_PATH: &str = "assets/tuning/movement.ron";
+const DEFAULT_TUNING_PRESET: &str = "classic";
+const FALL_TUNING_PATH: &str = "assets/tuning/fall.ron";
+const ENEMY_ARCHETYPES_PATH: &str = "assets/tuning/enemies.ron";
+const BOSSES_PATH: &str = "assets/tuning/bosses.ron";
+const FEEDBACK_TUNING_PATH: &str = "assets/tuning/feedback.ron";
+const ATTACK_TUNING_PATH: &str = "assets/tuning/attacks.ron";
+const SETTINGS_DIR: &str = "prince_of_oliver";
+const SETTINGS_FILE: &str = "settings.ron";
+const SAVE_SLOTS: usize = 3;
+const SAVE_VERSION: u32 = 2;
+const LEVELS: &[&str] = &["dungeon"];
+const WALL_JUMP_ITEM: &str = "wall_jump";
+const PERSONAL_BESTS_FILE: &str = "personal_bests.ron";
+const RESULTS_TUNING_PATH: &str = "assets/tuning/results.ron";
+const RUN_STATS_DIR: &str = "runs";
+const KEY_ITEM: &str = "key";
+const COLLECTIBLES: &[&str] = &[KEY_ITEM, WALL_JUMP_ITEM];
+const MIN_WINDOW_SCALE: u32 = 2;
+const MAX_WINDOW_SCALE: u32 = 5;
+const GAMEPAD_DEADZONE: f32 = 0.3;
+const GAMEPAD_VERTICAL_THRESHOLD: f32 = 0.5;
+
 const PRESENT_LAYER: usize = 1;
 const TORCH_GLOW_Y_OFFSET: f32 = 10.0;
 const TORCH_GLOW_BASE_ALPHA: f32 = 0.2;
@@ -40,10 +101,215 @@ enum AppState {
     #[default]
     Title,
     InGame,
+    Restarting,
     End,
 }
 
-#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Default, Eq, PartialEq, Hash, Debug, SubStates)]
+#[source(AppState = AppState::InGame)]
+enum PauseState {
+    #[default]
+    Running,
+    Paused,
+    Options,
+    Controls,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+enum Action {
+    MoveLeft,
+    MoveRight,
+    Up,
+    Down,
+    Jump,
+    Attack,
+    Throw,
+    Interact,
+    SetRespawn,
+    Pause,
+    Confirm,
+}
+
+impl Action {
+    const ALL: [Action; 11] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Up,
+        Action::Down,
+        Action::Jump,
+        Action::Attack,
+        Action::Throw,
+        Action::Interact,
+        Action::SetRespawn,
+        Action::Pause,
+        Action::Confirm,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Up => "Up / climb / parry",
+            Action::Down => "Down / crouch",
+            Action::Jump => "Jump",
+            Action::Attack => "Attack",
+            Action::Throw => "Throw dagger",
+            Action::Interact => "Pull",
+            Action::SetRespawn => "Set respawn",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+        }
+    }
+
+    fn default_keys(self) -> Vec<KeyCode> {
+        match self {
+            Action::MoveLeft => vec![KeyCode::KeyA, KeyCode::ArrowLeft],
+            Action::MoveRight => vec![KeyCode::KeyD, KeyCode::ArrowRight],
+            Action::Up => vec![KeyCode::KeyW, KeyCode::ArrowUp],
+            Action::Down => vec![KeyCode::KeyS, KeyCode::ArrowDown],
+            Action::Jump => vec![KeyCode::Space],
+            Action::Attack => vec![KeyCode::KeyE],
+            Action::Throw => vec![KeyCode::KeyG],
+            Action::Interact => vec![KeyCode::KeyF],
+            Action::SetRespawn => vec![KeyCode::KeyR],
+            Action::Pause => vec![KeyCode::Escape],
+            Action::Confirm => vec![KeyCode::Space, KeyCode::Enter],
+        }
+    }
+
+    fn default_buttons(self) -> Vec<GamepadButton> {
+        match self {
+            Action::MoveLeft => vec![GamepadButton::DPadLeft],
+            Action::MoveRight => vec![GamepadButton::DPadRight],
+            Action::Up => vec![GamepadButton::DPadUp],
+            Action::Down => vec![GamepadButton::DPadDown],
+            Action::Jump => vec![GamepadButton::South],
+            Action::Attack => vec![GamepadButton::West],
+            Action::Throw => vec![GamepadButton::North],
+            Action::Interact => vec![GamepadButton::RightTrigger],
+            Action::SetRespawn => vec![GamepadButton::Select],
+            Action::Pause => vec![GamepadButton::Start],
+            Action::Confirm => vec![GamepadButton::South],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum InputDevice {
+    #[default]
+    Keyboard,
+    Gamepad,
+}
+
+#[derive(Resource, Default)]
+struct ActionInput {
+    pressed: BTreeSet<Action>,
+    just_pressed: BTreeSet<Action>,
+    device: InputDevice,
+}
+
+impl ActionInput {
+    fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    fn axis(&self, negative: Action, positive: Action) -> f32 {
+        let mut value = 0.0;
+        if self.pressed(negative) {
+            value -= 1.0;
+        }
+        if self.pressed(positive) {
+            value += 1.0;
+        }
+        value
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MenuAction {
+    Continue,
+    NewGame,
+    SaveSlot,
+    Resume,
+    RestartLevel,
+    RestartCheckpoint,
+    Options,
+    QuitToTitle,
+    MusicVolume,
+    SfxVolume,
+    Fullscreen,
+    WindowScale,
+    Renderer,
+    Backend,
+    Labels,
+    ScreenEffects,
+    Controls,
+    Bind(Action),
+    ResetBindings,
+    Back,
+}
+
+impl MenuAction {
+    fn label(self, settings: &Settings, saves: &SaveSlots) -> String {
+        let on_off = |value: bool| if value { "On" } else { "Off" };
+        match self {
+            MenuAction::Continue if saves.current().is_some() => "Continue".to_string(),
+            MenuAction::Continue => "Continue (no save)".to_string(),
+            MenuAction::NewGame => "New game".to_string(),
+            MenuAction::SaveSlot => saves.label(),
+            MenuAction::Resume => "Resume".to_string(),
+            MenuAction::RestartLevel => "Restart level".to_string(),
+            MenuAction::RestartCheckpoint => "Restart from checkpoint".to_string(),
+            MenuAction::Options => "Options".to_string(),
+            MenuAction::QuitToTitle => "Quit to title".to_string(),
+            MenuAction::MusicVolume => {
+                format!("Music volume: {}%", (settings.music_volume * 100.0).round())
+            }
+            MenuAction::SfxVolume => {
+                format!("Sound volume: {}%", (settings.sfx_volume * 100.0).round())
+            }
+            MenuAction::Fullscreen => format!("Fullscreen: {}", on_off(settings.fullscreen)),
+            MenuAction::WindowScale => format!(
+                "Window size: {}x{}",
+                VIEW_WIDTH as u32 * settings.window_scale,
+                VIEW_HEIGHT as u32 * settings.window_scale
+            ),
+            MenuAction::Renderer => format!("Render mode: {} *", settings.render_mode.label()),
+            MenuAction::Backend => format!("Backend: {} *", settings.render_backend.label()),
+            MenuAction::Labels => format!("Labels: {} *", on_off(settings.labels)),
+            MenuAction::ScreenEffects => {
+                format!("Screen effects: {}", on_off(settings.screen_effects))
+            }
+            MenuAction::Controls => "Controls".to_string(),
+            MenuAction::Bind(action) => {
+                let keys = settings
+                    .bindings
+                    .get(&action)
+                    .map(|keys| keys.iter().map(|key| key_name(*key)).collect::<Vec<_>>())
+                    .unwrap_or_default();
+                let buttons = settings
+                    .gamepad_bindings
+                    .get(&action)
+                    .map(|buttons| {
+                        buttons
+                            .iter()
+                            .map(|button| button_name(*button))
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                format!("{}: {} / {}", action.label(), keys.join(", "), buttons.join(", "))
+            }
+            MenuAction::ResetBindings => "Reset to defaults".to_string(),
+            MenuAction::Back => "Back".to_string(),
+        }
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 enum RenderMode {
     Cpu,
     Gpu,
@@ -53,9 +319,16 @@ impl RenderMode {
     fn is_cpu(self) -> bool {
         matches!(self, RenderMode::Cpu)
     }
+
+    fn label(self) -> &'static str {
+        match self {
+            RenderMode::Cpu => "CPU",
+            RenderMode::Gpu => "GPU",
+        }
+    }
 }
 
-#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 enum RenderBackend {
     Auto,
     Gl,
@@ -70,6 +343,158 @@ impl RenderBackend {
             RenderBackend::Vulkan => Some(Backends::VULKAN),
         }
     }
+
+    fn label(self) -> &'static str {
+        match self {
+            RenderBackend::Auto => "Auto",
+            RenderBackend::Gl => "OpenGL",
+            RenderBackend::Vulkan => "Vulkan",
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct Settings {
+    music_volume: f32,
+    sfx_volume: f32,
+    fullscreen: bool,
+    window_scale: u32,
+    render_mode: RenderMode,
+    render_backend: RenderBackend,
+    labels: bool,
+    screen_effects: bool,
+    bindings: BTreeMap<Action, Vec<KeyCode>>,
+    gamepad_bindings: BTreeMap<Action, Vec<GamepadButton>>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            fullscreen: false,
+            window_scale: 3,
+            render_mode: RenderMode::Cpu,
+            render_backend: RenderBackend::Gl,
+            labels: false,
+            screen_effects: true,
+            bindings: default_bindings(),
+            gamepad_bindings: default_gamepad_bindings(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct SaveData {
+    version: u32,
+    level: String,
+    unlocked_levels: BTreeSet<String>,
+    checkpoint: Option<CheckpointSnapshot>,
+    collected: BTreeSet<String>,
+    best_times: BTreeMap<String, f32>,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        Self {
+            version: 0,
+            level: LEVELS[0].to_string(),
+            unlocked_levels: BTreeSet::from([LEVELS[0].to_string()]),
+            checkpoint: None,
+            collected: BTreeSet::new(),
+            best_times: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct SaveSlot {
+    data: Option<SaveData>,
+    error: Option<String>,
+}
+
+#[derive(Resource, Default)]
+struct SaveSlots {
+    selected: usize,
+    slots: Vec<SaveSlot>,
+    resume: bool,
+}
+
+impl SaveSlots {
+    fn current(&self) -> Option<&SaveData> {
+        self.slots.get(self.selected).and_then(|slot| slot.data.as_ref())
+    }
+
+    fn label(&self) -> String {
+        let summary = match self.slots.get(self.selected) {
+            Some(SaveSlot {
+                data: Some(data), ..
+            }) => {
+                let mut summary = data.level.clone();
+                if data.checkpoint.is_some() {
+                    summary.push_str(", checkpoint");
+                }
+                if let Some(best) = data.best_times.get(&data.level) {
+                    summary.push_str(&format!(", best {}", format_time(*best)));
+                }
+                summary
+            }
+            Some(SaveSlot {
+                error: Some(_), ..
+            }) => "Empty (unreadable save was backed up)".to_string(),
+            _ => "Empty".to_string(),
+        };
+        format!("Slot {}: {}", self.selected + 1, summary)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+enum Split {
+    Key,
+    Checkpoint,
+    LevelExit,
+}
+
+impl Split {
+    fn label(self) -> &'static str {
+        match self {
+            Split::Key => "Key",
+            Split::Checkpoint => "Checkpoint",
+            Split::LevelExit => "Exit",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct SplitTime {
+    split: Split,
+    time: f32,
+    delta: Option<f32>,
+}
+
+#[derive(Resource, Default)]
+struct LevelTimer {
+    elapsed: f32,
+    finished: bool,
+    resumed: bool,
+    splits: Vec<SplitTime>,
+    best: Option<f32>,
+    new_best: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PersonalBest {
+    total: f32,
+    splits: BTreeMap<Split, f32>,
+}
+
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PersonalBests {
+    levels: BTreeMap<String, PersonalBest>,
 }
 
 #[derive(Resource)]
@@ -87,8 +512,20 @@ struct GameAssets {
     torch: Handle<Image>,
     glow: Handle<Image>,
     player: Handle<Image>,
-    guard: Handle<Image>,
+    enemies: BTreeMap<String, Handle<Image>>,
+    arrow: Handle<Image>,
+    dart: Handle<Image>,
+    dart_trap: Handle<Image>,
+    dagger: Handle<Image>,
+    bosses: BTreeMap<String, Handle<Image>>,
+    orb: Handle<Image>,
     slash: Handle<Image>,
+    blade: Handle<Image>,
+    crate_block: Handle<Image>,
+    stone_block: Handle<Image>,
+    plate: Handle<Image>,
+    heart: Handle<Image>,
+    eye: Handle<Image>,
 }
 
 #[derive(Resource)]
@@ -99,6 +536,11 @@ struct AudioAssets {
     win: Handle<AudioSource>,
     alert: Handle<AudioSource>,
     jump: Handle<AudioSource>,
+    land: Handle<AudioSource>,
+    splash: Handle<AudioSource>,
+    hurt: Handle<AudioSource>,
+    clang: Handle<AudioSource>,
+    hit: Handle<AudioSource>,
 }
 
 #[derive(Resource)]
@@ -111,27 +553,547 @@ struct LabelSettings {
     enabled: bool,
 }
 
+#[derive(Resource, Clone, Copy)]
+struct AccessibilitySettings {
+    screen_effects: bool,
+}
+
 #[derive(Resource)]
 struct AtlasAssets {
     player: Handle<TextureAtlasLayout>,
-    guard: Handle<TextureAtlasLayout>,
+    enemies: BTreeMap<String, Handle<TextureAtlasLayout>>,
+    bosses: BTreeMap<String, Handle<TextureAtlasLayout>>,
     sofia: Handle<TextureAtlasLayout>,
+    eye: Handle<TextureAtlasLayout>,
 }
 
 #[derive(Resource, Default)]
 struct SessionState {
     has_key: bool,
     hud_key_icon: Option<Entity>,
+    hud_air_bar: Option<Entity>,
+    hud_air_fill: Option<Entity>,
+    hud_hearts: Vec<Entity>,
+    hud_eye: Option<Entity>,
+    hud_daggers: Option<Entity>,
+    hud_timer: Option<Entity>,
+    hud_split: Option<Entity>,
+    hud_boss_bar: Option<Entity>,
+    hud_boss_fill: Option<Entity>,
+    hud_boss_name: Option<Entity>,
+    wall_jump_unlocked: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+struct PlayerTuning {
+    speed: f32,
+    jump_velocity: f32,
+    gravity: f32,
+    coyote_time: f32,
+    jump_buffer: f32,
+    climb_speed: f32,
+    jump_cut: f32,
+    fall_gravity_scale: f32,
+    apex_gravity_scale: f32,
+    apex_threshold: f32,
+    max_fall_speed: f32,
+    swim_speed: f32,
+    swim_stroke: f32,
+    air_time: f32,
+}
+
+impl Default for PlayerTuning {
+    fn default() -> Self {
+        Self {
+            speed: 90.0,
+            jump_velocity: 190.0,
+            gravity: -520.0,
+            coyote_time: 0.12,
+            jump_buffer: 0.12,
+            climb_speed: 60.0,
+            jump_cut: 0.45,
+            fall_gravity_scale: 1.6,
+            apex_gravity_scale: 0.55,
+            apex_threshold: 30.0,
+            max_fall_speed: 320.0,
+            swim_speed: 50.0,
+            swim_stroke: 150.0,
+            air_time: 6.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+struct GuardTuning {
+    speed: f32,
+    gravity: f32,
+    climb_speed: f32,
+}
+
+impl Default for GuardTuning {
+    fn default() -> Self {
+        Self {
+            speed: 40.0,
+            gravity: -520.0,
+            climb_speed: 30.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(default)]
+struct MovementPreset {
+    player: PlayerTuning,
+    guard: GuardTuning,
+}
+
+#[derive(Resource, Debug, Default, Deserialize)]
+#[serde(default)]
+struct MovementTuning {
+    presets: BTreeMap<String, MovementPreset>,
+}
+
+impl MovementTuning {
+    fn preset(&self, name: &str) -> MovementPreset {
+        match self.presets.get(name) {
+            Some(preset) => *preset,
+            None => {
+                warn!("Unknown movement preset '{}', using defaults", name);
+                MovementPreset::default()
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+struct TuningWatcher {
+    modified: Option<SystemTime>,
+    timer: Timer,
+}
+
+#[derive(Resource, Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+struct FallTuning {
+    stun_distance: f32,
+    hurt_distance: f32,
+    fatal_distance: f32,
+    stun_time: f32,
+    hurt_time: f32,
+}
+
+impl Default for FallTuning {
+    fn default() -> Self {
+        Self {
+            stun_distance: 56.0,
+            hurt_distance: 96.0,
+            fatal_distance: 150.0,
+            stun_time: 0.3,
+            hurt_time: 0.8,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug, Deserialize)]
+#[serde(default)]
+struct ResultsTuning {
+    par_time: f32,
+    death_penalty: f32,
+    late_penalty: f32,
+    missed_collectible_penalty: f32,
+    grades: Vec<(String, f32)>,
+    fallback_grade: String,
+}
+
+impl Default for ResultsTuning {
+    fn default() -> Self {
+        Self {
+            par_time: 150.0,
+            death_penalty: 12.0,
+            late_penalty: 40.0,
+            missed_collectible_penalty: 10.0,
+            grades: vec![
+                ("S".to_string(), 95.0),
+                ("A".to_string(), 85.0),
+                ("B".to_string(), 70.0),
+                ("C".to_string(), 50.0),
+            ],
+            fallback_grade: "D".to_string(),
+        }
+    }
+}
+
+impl ResultsTuning {
+    fn score(&self, stats: &RunStats, timer: &LevelTimer) -> f32 {
+        let late = ((timer.elapsed - self.par_time) / self.par_time).clamp(0.0, 1.0);
+        let missed = COLLECTIBLES.len().saturating_sub(stats.collected.len());
+        (100.0
+            - stats.total_deaths() as f32 * self.death_penalty
+            - late * self.late_penalty
+            - missed as f32 * self.missed_collectible_penalty)
+            .max(0.0)
+    }
+
+    fn grade(&self, score: f32) -> &str {
+        self.grades
+            .iter()
+            .find(|(_, threshold)| score >= *threshold)
+            .map_or(self.fallback_grade.as_str(), |(grade, _)| grade.as_str())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+struct ImpactTuning {
+    hit_stop: f32,
+    shake: f32,
+    flash: f32,
+    particles: u32,
+    particle_speed: f32,
+    color: (f32, f32, f32),
+}
+
+impl Default for ImpactTuning {
+    fn default() -> Self {
+        Self {
+            hit_stop: 0.05,
+            shake: 0.25,
+            flash: 0.12,
+            particles: 6,
+            particle_speed: 80.0,
+            color: (1.0, 0.9, 0.6),
+        }
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+struct FeedbackTuning {
+    hit_stop_scale: f32,
+    shake_scale: f32,
+    flash_scale: f32,
+    particle_scale: f32,
+    max_shake: f32,
+    shake_decay: f32,
+    hit: ImpactTuning,
+    parry: ImpactTuning,
+    kill: ImpactTuning,
+    hurt: ImpactTuning,
+    death: ImpactTuning,
+}
+
+impl Default for FeedbackTuning {
+    fn default() -> Self {
+        Self {
+            hit_stop_scale: 1.0,
+            shake_scale: 1.0,
+            flash_scale: 1.0,
+            particle_scale: 1.0,
+            max_shake: 6.0,
+            shake_decay: 1.6,
+            hit: ImpactTuning::default(),
+            parry: ImpactTuning {
+                hit_stop: 0.08,
+                shake: 0.35,
+                flash: 0.0,
+                particles: 8,
+                particle_speed: 100.0,
+                color: (0.75, 0.9, 1.0),
+            },
+            kill: ImpactTuning {
+                hit_stop: 0.12,
+                shake: 0.6,
+                flash: 0.2,
+                particles: 14,
+                particle_speed: 110.0,
+                color: (1.0, 0.75, 0.45),
+            },
+            hurt: ImpactTuning {
+                hit_stop: 0.06,
+                shake: 0.5,
+                flash: 0.15,
+                particles: 8,
+                particle_speed: 70.0,
+                color: (1.0, 0.35, 0.3),
+            },
+            death: ImpactTuning {
+                hit_stop: 0.2,
+                shake: 0.9,
+                flash: 0.0,
+                particles: 16,
+                particle_speed: 90.0,
+                color: (1.0, 0.35, 0.3),
+            },
+        }
+    }
+}
+
+impl FeedbackTuning {
+    fn impact(&self, kind: ImpactKind) -> ImpactTuning {
+        match kind {
+            ImpactKind::Hit => self.hit,
+            ImpactKind::Parry => self.parry,
+            ImpactKind::Kill => self.kill,
+            ImpactKind::Hurt => self.hurt,
+            ImpactKind::Death => self.death,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct HitboxFrame {
+    start: f32,
+    end: f32,
+    offset: (f32, f32),
+    size: (f32, f32),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+struct AttackMove {
+    duration: f32,
+    damage: u32,
+    bounce: f32,
+    slash: (f32, f32, f32),
+    hitboxes: Vec<HitboxFrame>,
+}
+
+impl Default for AttackMove {
+    fn default() -> Self {
+        Self {
+            duration: 0.43,
+            damage: 1,
+            bounce: 0.0,
+            slash: (14.0, 4.0, 0.0),
+            hitboxes: vec![HitboxFrame {
+                start: 0.08,
+                end: 0.26,
+                offset: (12.0, -4.0),
+                size: (18.0, 10.0),
+            }],
+        }
+    }
+}
+
+impl AttackMove {
+    fn windup(&self, time: f32) -> f32 {
+        let first = self
+            .hitboxes
+            .iter()
+            .map(|hitbox| hitbox.start)
+            .fold(self.duration, f32::min);
+        (first - time).max(0.0)
+    }
+
+    fn hitbox(&self, time: f32) -> Option<&HitboxFrame> {
+        self.hitboxes
+            .iter()
+            .find(|hitbox| time >= hitbox.start && time < hitbox.end)
+    }
+}
+
+#[derive(Resource, Clone, Debug, Deserialize)]
+#[serde(default)]
+struct AttackTuning {
+    buffer: f32,
+    combo_window: f32,
+    combo: Vec<AttackMove>,
+    up: AttackMove,
+    down: AttackMove,
+    crouch: AttackMove,
+}
+
+impl Default for AttackTuning {
+    fn default() -> Self {
+        Self {
+            buffer: 0.2,
+            combo_window: 0.3,
+            combo: vec![AttackMove::default()],
+            up: AttackMove::default(),
+            down: AttackMove::default(),
+            crouch: AttackMove::default(),
+        }
+    }
+}
+
+impl AttackTuning {
+    fn combo_move(&self, step: usize) -> AttackMove {
+        self.combo.get(step).cloned().unwrap_or_default()
+    }
+}
+
+#[derive(Resource, Default)]
+struct HitStop {
+    remaining: f32,
+}
+
+#[derive(Resource, Default)]
+struct CameraShake {
+    trauma: f32,
+    time: f32,
+    offset: Vec2,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct RangedAttack {
+    preferred_range: f32,
+    projectile_speed: f32,
+    windup: f32,
+    cooldown: f32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+struct EnemyArchetype {
+    sprite: String,
+    frame_size: (u32, u32),
+    frames: u32,
+    collider: (f32, f32),
+    health: u32,
+    speed_scale: f32,
+    duel_speed: f32,
+    windup: f32,
+    parry_chance: f32,
+    knockback: f32,
+    backstab: bool,
+    ranged: Option<RangedAttack>,
+    revive_time: Option<f32>,
+    aim_frame: Option<usize>,
+    down_frame: Option<usize>,
+}
+
+impl Default for EnemyArchetype {
+    fn default() -> Self {
+        Self {
+            sprite: "assets/guard.png".to_string(),
+            frame_size: (24, 24),
+            frames: 2,
+            collider: (14.0, 24.0),
+            health: 3,
+            speed_scale: 1.0,
+            duel_speed: 28.0,
+            windup: 0.35,
+            parry_chance: 0.45,
+            knockback: 40.0,
+            backstab: true,
+            ranged: None,
+            revive_time: None,
+            aim_frame: None,
+            down_frame: None,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default, Deserialize)]
+#[serde(default)]
+struct EnemyArchetypes {
+    archetypes: BTreeMap<String, EnemyArchetype>,
+}
+
+impl EnemyArchetypes {
+    fn archetype(&self, name: &str) -> EnemyArchetype {
+        match self.archetypes.get(name) {
+            Some(archetype) => archetype.clone(),
+            None => {
+                warn!("Unknown enemy archetype '{}', using defaults", name);
+                EnemyArchetype::default()
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+enum BossAction {
+    Wait {
+        duration: f32,
+    },
+    Walk {
+        duration: f32,
+    },
+    Strike {
+        windup: f32,
+        reach: f32,
+        damage: u32,
+    },
+    Volley {
+        windup: f32,
+        count: u32,
+        spread: f32,
+        speed: f32,
+    },
+    Dash {
+        windup: f32,
+        speed: f32,
+        duration: f32,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct BossPhase {
+    until: f32,
+    speed: f32,
+    pattern: Vec<BossAction>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct BossDef {
+    name: String,
+    sprite: String,
+    frame_size: (u32, u32),
+    frames: u32,
+    collider: (f32, f32),
+    health: u32,
+    phases: Vec<BossPhase>,
+}
+
+#[derive(Resource, Debug, Default, Deserialize)]
+#[serde(default)]
+struct BossLibrary {
+    bosses: BTreeMap<String, BossDef>,
+}
+
+#[derive(Resource, Clone)]
+struct LevelRules {
+    wall_jump: bool,
+    tuning_preset: String,
 }
 
 #[derive(Resource)]
 struct GuardSpawns(Vec<GuardSpawn>);
 
+#[derive(Resource)]
+struct BossSpawns(Vec<BossSpawn>);
+
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct CheckpointSnapshot {
+    dead_guards: Vec<String>,
+    defeated_bosses: Vec<String>,
+    blocks: Vec<(String, Vec2)>,
+    has_key: bool,
+    respawn: Option<Vec2>,
+    time: f32,
+}
+
+#[derive(Clone, Copy)]
+struct BossSpawn {
+    boss: &'static str,
+    pos: Vec2,
+    arena_left: f32,
+    arena_right: f32,
+    entry_gate: Vec2,
+    exit_gate: Vec2,
+    label: &'static str,
+}
+
 #[derive(Clone, Copy)]
 struct GuardSpawn {
     pos: Vec2,
     left: f32,
     right: f32,
+    leash: f32,
+    archetype: &'static str,
     label: &'static str,
 }
 
@@ -143,16 +1105,57 @@ struct PlayerState {
     speed: f32,
     jump_velocity: f32,
     gravity: f32,
+    jump_cut: f32,
+    fall_gravity_scale: f32,
+    apex_gravity_scale: f32,
+    apex_threshold: f32,
+    max_fall_speed: f32,
+    swim_speed: f32,
+    swim_stroke: f32,
+    air_time: f32,
+    air: f32,
+    in_water: bool,
     coyote_time: f32,
     jump_buffer: f32,
     climb_speed: f32,
     coyote_timer: f32,
     jump_buffer_timer: f32,
+    jump_held: bool,
+    grounded: bool,
+    fall_apex_y: f32,
+    land_stun_timer: f32,
+    stand_height: f32,
+    crouch_height: f32,
+    crouched: bool,
+    pushing: bool,
+    pulling: bool,
+    push_speed: f32,
+    hurt_timer: f32,
+    invincible_timer: f32,
+    invincible_time: f32,
+    knockback: Vec2,
     on_ladder: bool,
+    wall_jump_enabled: bool,
+    wall_slide_speed: f32,
+    wall_jump_velocity: Vec2,
+    wall_jump_lock: f32,
+    wall_jump_lock_timer: f32,
+    wall_dir: f32,
     respawn_position: Vec2,
     walk_timer: f32,
-    attack_cooldown: f32,
-    attack_active: f32,
+    attack: Option<AttackMove>,
+    attack_timer: f32,
+    attack_windup: f32,
+    attack_connected: bool,
+    attack_hitbox: Option<(Vec2, Vec2)>,
+    attack_buffer: f32,
+    combo_step: usize,
+    combo_timer: f32,
+    daggers: u32,
+    parry_timer: f32,
+    parry_cooldown: f32,
+    stagger_timer: f32,
+    duel_speed: f32,
     facing: f32,
     slash_entity: Entity,
 }
@@ -163,42 +1166,256 @@ struct Velocity(Vec2);
 #[derive(Component)]
 struct Guard {
     speed: f32,
+    speed_scale: f32,
     gravity: f32,
+    climb_speed: f32,
+    climb_dir: f32,
+    home_y: f32,
     left_limit: f32,
     right_limit: f32,
+    leash: f32,
     direction: f32,
     walk_timer: f32,
     alive: bool,
+    state: GuardState,
+    state_timer: f32,
+    lost_timer: f32,
+    awareness: f32,
+    duel_speed: f32,
+    windup: f32,
+    windup_timer: f32,
+    strike_cooldown: f32,
+    parry_chance: f32,
+    parry_timer: f32,
+    stagger_timer: f32,
+    knockback: f32,
+    backstab: bool,
+    ranged: Option<RangedAttack>,
+    fire_cooldown: f32,
+    revive_time: Option<f32>,
+    downed_timer: f32,
+    aim_frame: Option<usize>,
+    down_frame: Option<usize>,
+    reacted: bool,
+    rng: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ProjectileOwner {
+    Player,
+    Enemy,
+    Trap,
 }
 
 #[derive(Component)]
-struct Collider {
+struct Projectile {
+    velocity: Vec2,
+    gravity_scale: f32,
+    lifetime: f32,
+    owner: ProjectileOwner,
+    damage: Damage,
     size: Vec2,
 }
 
 #[derive(Component)]
-struct Solid;
+struct Boss {
+    name: String,
+    phases: Vec<BossPhase>,
+    phase: usize,
+    step: usize,
+    timer: f32,
+    done: bool,
+    active: bool,
+    defeated: bool,
+    arena_left: f32,
+    arena_right: f32,
+    direction: f32,
+    stagger_timer: f32,
+    hit_timer: f32,
+}
 
 #[derive(Component)]
-struct Hazard;
+struct ArenaGate {
+    boss: Entity,
+}
 
 #[derive(Component)]
-struct Ladder;
+struct DartTrap {
+    direction: f32,
+    interval: f32,
+    timer: f32,
+    speed: f32,
+}
 
-#[derive(Component)]
-struct Key;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GuardState {
+    Patrol,
+    Alert,
+    Chase,
+    Attack,
+    Return,
+}
+
+impl GuardState {
+    fn label(self) -> &'static str {
+        match self {
+            GuardState::Patrol => "patrol",
+            GuardState::Alert => "alert",
+            GuardState::Chase => "chase",
+            GuardState::Attack => "attack",
+            GuardState::Return => "return",
+        }
+    }
+
+    fn is_aware(self) -> bool {
+        matches!(self, GuardState::Alert | GuardState::Chase | GuardState::Attack)
+    }
+}
+
+#[derive(Component)]
+struct GuardStateLabel;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SwordHit {
+    Miss,
+    Hit,
+    Parried,
+}
+
+#[derive(Component)]
+struct Collider {
+    size: Vec2,
+}
+
+#[derive(Component)]
+struct Solid;
+
+#[derive(Component)]
+struct Health {
+    current: u32,
+    max: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Damage {
+    Hit(u32),
+    Kill,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum DeathCause {
+    Spikes,
+    KillZone,
+    Guard,
+    Projectile,
+    Trap,
+    Boss,
+    Fall,
+    Drowning,
+}
+
+impl DeathCause {
+    const ALL: [DeathCause; 8] = [
+        DeathCause::Spikes,
+        DeathCause::KillZone,
+        DeathCause::Guard,
+        DeathCause::Projectile,
+        DeathCause::Trap,
+        DeathCause::Boss,
+        DeathCause::Fall,
+        DeathCause::Drowning,
+    ];
+
+    fn key(self) -> &'static str {
+        match self {
+            DeathCause::Spikes => "spikes",
+            DeathCause::KillZone => "kill_zone",
+            DeathCause::Guard => "guard",
+            DeathCause::Projectile => "projectile",
+            DeathCause::Trap => "trap",
+            DeathCause::Boss => "boss",
+            DeathCause::Fall => "fall",
+            DeathCause::Drowning => "drowning",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            DeathCause::KillZone => "pit",
+            other => other.key(),
+        }
+    }
+}
+
+#[derive(Component)]
+struct Hazard {
+    damage: Damage,
+    cause: DeathCause,
+}
+
+#[derive(Component)]
+struct Ladder;
+
+#[derive(Component)]
+struct Water {
+    buoyancy: f32,
+    drag: f32,
+}
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+    gravity: f32,
+}
+
+#[derive(Component)]
+struct Block {
+    push_speed: f32,
+    pull_speed: f32,
+    home: Vec2,
+}
+
+#[derive(Component)]
+struct PressurePlate {
+    gate: Entity,
+    pressed: bool,
+}
+
+#[derive(Component)]
+struct Gate {
+    closed_y: f32,
+    open_y: f32,
+    open: bool,
+}
+
+#[derive(Component)]
+struct BladeTrap {
+    base_x: f32,
+    range: f32,
+    phase: f32,
+}
+
+#[derive(Component)]
+struct Key;
 
 #[derive(Component)]
 struct Gap;
 
 #[derive(Component)]
-struct Door;
+struct Door {
+    closed_y: f32,
+}
 
 #[derive(Component)]
 struct DoorBlocker;
 
 #[derive(Component)]
-struct Checkpoint;
+struct Checkpoint {
+    occupied: bool,
+}
+
+#[derive(Component)]
+struct WallJumpUpgrade;
 
 #[derive(Component)]
 struct Princess;
@@ -220,6 +1437,11 @@ struct TorchLight {
     phase: f32,
 }
 
+#[derive(Component)]
+struct LightSource {
+    radius: f32,
+}
+
 #[derive(Component)]
 struct DoorOpening {
     start: f32,
@@ -232,11 +1454,122 @@ struct FadeOut {
     timer: Timer,
 }
 
+#[derive(Component)]
+struct HitFlash {
+    timer: Timer,
+    color: Color,
+}
+
 #[derive(Component)]
 struct Slash;
 
 #[derive(Message)]
-struct RespawnEvent;
+struct RespawnEvent {
+    cause: Option<DeathCause>,
+}
+
+#[derive(Message)]
+struct SplitEvent(Split);
+
+#[derive(Message, Clone, Copy)]
+enum AutosaveEvent {
+    Checkpoint,
+    LevelComplete,
+}
+
+#[derive(Message)]
+struct PlayerHitEvent {
+    damage: Damage,
+    from: Vec2,
+    cause: DeathCause,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ImpactKind {
+    Hit,
+    Parry,
+    Kill,
+    Hurt,
+    Death,
+}
+
+#[derive(Message)]
+struct ImpactEvent {
+    kind: ImpactKind,
+    pos: Vec2,
+    dir: f32,
+    target: Option<Entity>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NoiseKind {
+    Jump,
+    Landing,
+}
+
+#[derive(Message)]
+struct NoiseEvent {
+    pos: Vec2,
+    radius: f32,
+    kind: NoiseKind,
+}
+
+#[derive(Message)]
+struct CollectEvent(&'static str);
+
+#[derive(Message)]
+struct GuardDefeatedEvent;
+
+#[derive(Resource, Default)]
+struct RunStats {
+    deaths: BTreeMap<DeathCause, u32>,
+    guards_defeated: u32,
+    jumps: u32,
+    collected: BTreeSet<&'static str>,
+}
+
+impl RunStats {
+    fn total_deaths(&self) -> u32 {
+        self.deaths.values().sum()
+    }
+}
+
+#[derive(Serialize)]
+struct RunStatsExport<'a> {
+    level: &'a str,
+    slot: usize,
+    finished_at: u64,
+    time: f32,
+    resumed: bool,
+    personal_best: bool,
+    deaths: DeathsExport,
+    guards_defeated: u32,
+    jumps: u32,
+    collectibles: CollectiblesExport<'a>,
+    splits: Vec<SplitExport>,
+    score: f32,
+    grade: &'a str,
+}
+
+#[derive(Serialize)]
+struct DeathsExport {
+    total: u32,
+    #[serde(flatten)]
+    causes: BTreeMap<&'static str, u32>,
+}
+
+#[derive(Serialize)]
+struct CollectiblesExport<'a> {
+    collected: Vec<&'a str>,
+    total: usize,
+}
+
+#[derive(Serialize)]
+struct SplitExport {
+    split: &'static str,
+    time: f32,
+    delta: Option<f32>,
+}
 
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
 struct InGameSet;
@@ -250,10 +1583,300 @@ struct EndUi;
 #[derive(Component)]
 struct GameCamera;
 
+#[derive(Component)]
+struct Music;
+
+#[derive(Component)]
+struct PromptText {
+    template: &'static str,
+    actions: &'static [Action],
+}
+
+#[derive(Component)]
+struct MenuItem {
+    action: MenuAction,
+    index: usize,
+}
+
+#[derive(Resource, Default)]
+struct MenuCursor {
+    selected: usize,
+    capturing: bool,
+}
+
+#[derive(SystemParam)]
+struct MenuContext<'w> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    actions: Res<'w, ActionInput>,
+    state: Option<Res<'w, State<PauseState>>>,
+    cursor: ResMut<'w, MenuCursor>,
+    settings: ResMut<'w, Settings>,
+    saves: ResMut<'w, SaveSlots>,
+    session: ResMut<'w, SessionState>,
+    next_app_state: ResMut<'w, NextState<AppState>>,
+    next_pause_state: ResMut<'w, NextState<PauseState>>,
+    respawn_writer: MessageWriter<'w, RespawnEvent>,
+}
+
+type Colliders<'w, 's, F> = Query<'w, 's, (&'static Transform, &'static Collider), F>;
+
+type GuardTargets<'w, 's, F> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        &'static mut Guard,
+        &'static Collider,
+        &'static mut Velocity,
+        &'static mut Health,
+    ),
+    F,
+>;
+
+type PlayerBody<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Transform,
+        &'static mut Sprite,
+        &'static mut PlayerState,
+        &'static mut Velocity,
+        &'static mut Collider,
+    ),
+    With<Player>,
+>;
+
+type GuardBodies<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Transform,
+        &'static mut Sprite,
+        &'static mut Guard,
+        &'static mut Velocity,
+        &'static Collider,
+        &'static mut Health,
+    ),
+    With<Guard>,
+>;
+
+type BossBodies<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Transform,
+        &'static mut Sprite,
+        &'static mut Boss,
+        &'static mut Velocity,
+        &'static Collider,
+        &'static mut Health,
+    ),
+    Without<Player>,
+>;
+
+type BossTargets<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        &'static Collider,
+        &'static mut Boss,
+        &'static mut Health,
+    ),
+    (Without<Guard>, Without<Projectile>),
+>;
+
+type PlayerTarget<'w, 's, F> = Query<
+    'w,
+    's,
+    (
+        &'static Transform,
+        &'static Collider,
+        &'static mut PlayerState,
+        &'static mut Velocity,
+    ),
+    (With<Player>, F),
+>;
+
+type RespawnedPlayer<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Transform,
+        &'static mut Velocity,
+        &'static mut PlayerState,
+        &'static mut Collider,
+        &'static mut Health,
+    ),
+    With<Player>,
+>;
+
+type PlayerSight<'w, 's> =
+    Query<'w, 's, (&'static Transform, &'static PlayerState), (With<Player>, Without<Guard>)>;
+
+type ArenaEntities<'w, 's> = Query<'w, 's, Entity, Or<(With<Boss>, With<ArenaGate>)>>;
+
+type SlashSprite<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Transform,
+        &'static mut Sprite,
+        &'static mut Visibility,
+    ),
+    (
+        With<Slash>,
+        Without<Player>,
+        Without<Guard>,
+        Without<Solid>,
+        Without<Ladder>,
+    ),
+>;
+
+#[derive(SystemParam)]
+struct LevelAssets<'w> {
+    assets: Res<'w, GameAssets>,
+    atlases: Res<'w, AtlasAssets>,
+    render_mode: Res<'w, RenderMode>,
+    ui: Res<'w, UiAssets>,
+    labels: Res<'w, LabelSettings>,
+}
+
+#[derive(SystemParam)]
+struct LevelData<'w> {
+    guard_spawns: Res<'w, GuardSpawns>,
+    archetypes: Res<'w, EnemyArchetypes>,
+    boss_spawns: Res<'w, BossSpawns>,
+    bosses: Res<'w, BossLibrary>,
+    rules: Res<'w, LevelRules>,
+    movement_tuning: Res<'w, MovementTuning>,
+}
+
+#[derive(SystemParam)]
+struct ActorContext<'w> {
+    time: Res<'w, Time>,
+    assets: Res<'w, GameAssets>,
+    audio: Res<'w, AudioAssets>,
+}
+
+#[derive(SystemParam)]
+struct CombatEvents<'w> {
+    hit_writer: MessageWriter<'w, PlayerHitEvent>,
+    impact_writer: MessageWriter<'w, ImpactEvent>,
+}
+
+#[derive(SystemParam)]
+struct PlayerParams<'w> {
+    actions: Res<'w, ActionInput>,
+    fall: Res<'w, FallTuning>,
+    attacks: Res<'w, AttackTuning>,
+    noise_writer: MessageWriter<'w, NoiseEvent>,
+}
+
+#[derive(SystemParam)]
+struct PlayerWorld<'w, 's> {
+    solids: Colliders<'w, 's, (With<Solid>, Without<Player>)>,
+    ladders: Colliders<'w, 's, (With<Ladder>, Without<Player>)>,
+    waters: Query<'w, 's, (&'static Transform, &'static Collider, &'static Water), Without<Player>>,
+    slash_q: SlashSprite<'w, 's>,
+}
+
+#[derive(SystemParam)]
+struct GuardWorld<'w, 's> {
+    player_q: PlayerSight<'w, 's>,
+    lights: Query<'w, 's, (&'static Transform, &'static LightSource), Without<Guard>>,
+    solids: Colliders<'w, 's, (With<Solid>, Without<Guard>)>,
+    ladders: Colliders<'w, 's, (With<Ladder>, Without<Guard>)>,
+}
+
+#[derive(SystemParam)]
+struct RespawnWorld<'w, 's> {
+    guards: Query<'w, 's, Entity, With<Guard>>,
+    blocks: Query<
+        'w,
+        's,
+        (
+            &'static mut Transform,
+            &'static mut Velocity,
+            &'static Block,
+            &'static Name,
+        ),
+        Without<Player>,
+    >,
+    slash_q: Query<'w, 's, &'static mut Visibility, (With<Slash>, Without<Player>)>,
+}
+
+#[derive(SystemParam)]
+struct RestoreWorld<'w, 's> {
+    keys: Query<'w, 's, Entity, With<Key>>,
+    door_blockers: Query<'w, 's, Entity, With<DoorBlocker>>,
+    doors: Query<'w, 's, (Entity, &'static mut Transform, &'static Door)>,
+    arena: ArenaEntities<'w, 's>,
+    hud_icons: Query<'w, 's, &'static mut ImageNode>,
+}
+
+#[derive(SystemParam)]
+struct ProjectileTargets<'w, 's> {
+    player_q: Colliders<'w, 's, (With<Player>, Without<Projectile>)>,
+    guards: GuardTargets<'w, 's, (With<Guard>, Without<Projectile>)>,
+    bosses: BossTargets<'w, 's>,
+}
+
+#[derive(SystemParam)]
+struct ProgressEvents<'w> {
+    splits: MessageWriter<'w, SplitEvent>,
+    collects: MessageWriter<'w, CollectEvent>,
+    autosave: MessageWriter<'w, AutosaveEvent>,
+}
+
+#[derive(SystemParam)]
+struct KeyDoorWorld<'w, 's> {
+    key_q: Query<'w, 's, (Entity, &'static Transform, &'static Collider), With<Key>>,
+    door_q: Query<'w, 's, (Entity, &'static Transform), With<Door>>,
+    door_blockers: Query<'w, 's, Entity, With<DoorBlocker>>,
+    hud_icons: Query<'w, 's, &'static mut ImageNode>,
+}
+
+#[derive(SystemParam)]
+struct CheckpointWorld<'w, 's> {
+    checkpoint_q: Query<
+        'w,
+        's,
+        (
+            &'static Transform,
+            &'static Collider,
+            &'static mut Checkpoint,
+        ),
+    >,
+    guards: Query<'w, 's, (&'static Guard, &'static Name)>,
+    bosses: Query<'w, 's, (&'static Boss, &'static Name)>,
+    blocks: Query<'w, 's, (&'static Transform, &'static Name), With<Block>>,
+}
+
+#[derive(SystemParam)]
+struct RunResults<'w> {
+    timer: Res<'w, LevelTimer>,
+    stats: Res<'w, RunStats>,
+    tuning: Res<'w, ResultsTuning>,
+}
+
 fn main() {
-    let render_mode = render_mode_from_args();
-    let render_backend = render_backend_from_args();
-    let labels_enabled = labels_enabled_from_args();
+    let settings = load_settings();
+    let render_mode = render_mode_from_args(settings.render_mode);
+    let render_backend = render_backend_from_args(settings.render_backend);
+    let labels_enabled = settings.labels || labels_enabled_from_args();
+    let screen_effects = settings.screen_effects && screen_effects_from_args();
+    let movement_tuning: MovementTuning = load_tuning(MOVEMENT_TUNING_PATH);
+    let fall_tuning: FallTuning = load_tuning(FALL_TUNING_PATH);
+    let enemy_archetypes: EnemyArchetypes = load_tuning(ENEMY_ARCHETYPES_PATH);
+    let boss_library: BossLibrary = load_tuning(BOSSES_PATH);
+    let feedback_tuning: FeedbackTuning = load_tuning(FEEDBACK_TUNING_PATH);
+    let attack_tuning: AttackTuning = load_tuning(ATTACK_TUNING_PATH);
+    let results_tuning: ResultsTuning = load_tuning(RESULTS_TUNING_PATH);
     let render_plugin = if render_mode.is_cpu() || render_backend != RenderBackend::Auto {
         RenderPlugin {
             render_creation: WgpuSettings {
@@ -277,7 +1900,7 @@ fn main() {
     app.add_plugins(
         DefaultPlugins
             .set(AssetPlugin {
-                file_path: "..".to_string(),
+                file_path: ASSET_ROOT.to_string(),
                 ..default()
             })
             .set(render_plugin)
@@ -285,7 +1908,8 @@ fn main() {
             .set(WindowPlugin {
                 primary_window: Some(Window {
                     title: "Prince of Oliver".to_string(),
-                    resolution: (1280, 720).into(),
+                    resolution: window_resolution(&settings).into(),
+                    mode: window_mode(&settings),
                     resizable: true,
                     ..default()
                 }),
@@ -298,41 +1922,132 @@ fn main() {
         .insert_resource(LabelSettings {
             enabled: labels_enabled,
         })
+        .insert_resource(AccessibilitySettings { screen_effects })
+        .insert_resource(settings)
+        .insert_resource(movement_tuning)
+        .insert_resource(TuningWatcher {
+            modified: file_modified(&asset_file_path(MOVEMENT_TUNING_PATH)),
+            timer: Timer::from_seconds(0.5, TimerMode::Repeating),
+        })
+        .insert_resource(fall_tuning)
+        .insert_resource(enemy_archetypes)
+        .insert_resource(boss_library)
+        .insert_resource(feedback_tuning)
+        .insert_resource(attack_tuning)
+        .insert_resource(results_tuning)
         .init_state::<AppState>()
+        .add_sub_state::<PauseState>()
         .init_resource::<SessionState>()
+        .init_resource::<CheckpointSnapshot>()
+        .init_resource::<HitStop>()
+        .init_resource::<CameraShake>()
+        .init_resource::<MenuCursor>()
+        .init_resource::<ActionInput>()
+        .init_resource::<LevelTimer>()
+        .init_resource::<RunStats>()
         .add_message::<RespawnEvent>()
-        .add_systems(Startup, setup)
+        .add_message::<PlayerHitEvent>()
+        .add_message::<NoiseEvent>()
+        .add_message::<ImpactEvent>()
+        .add_message::<AutosaveEvent>()
+        .add_message::<SplitEvent>()
+        .add_message::<CollectEvent>()
+        .add_message::<GuardDefeatedEvent>()
+        .add_systems(Startup, (setup, load_save_slots, load_personal_bests))
+        .add_systems(PreUpdate, action_input_system.after(InputSystems))
         .add_systems(OnEnter(AppState::InGame), spawn_level)
+        .add_systems(OnEnter(AppState::Restarting), restart_level)
+        .add_systems(OnEnter(AppState::End), export_run_stats)
+        .add_systems(OnExit(PauseState::Running), pause_game)
+        .add_systems(OnEnter(PauseState::Running), resume_game)
+        .add_systems(OnExit(AppState::InGame), resume_game)
+        .add_systems(OnEnter(PauseState::Paused), spawn_pause_menu)
+        .add_systems(OnEnter(PauseState::Options), spawn_options_menu)
+        .add_systems(OnEnter(PauseState::Controls), spawn_controls_menu)
         .add_systems(
             Update,
             (
-                title_input.run_if(in_state(AppState::Title)),
                 end_input.run_if(in_state(AppState::End)),
                 spawn_title_ui.run_if(in_state(AppState::Title)),
                 spawn_end_ui.run_if(in_state(AppState::End)),
+                prompt_text_system
+                    .after(spawn_title_ui)
+                    .after(spawn_end_ui),
+                gamepad_connection_system,
+                tuning_reload_system,
+                hit_stop_system,
+                pause_input.run_if(in_state(AppState::InGame)),
+                menu_system
+                    .run_if(not(in_state(PauseState::Running)))
+                    .after(pause_input)
+                    .after(spawn_title_ui),
+                apply_settings_system,
             ),
         )
-        .configure_sets(Update, InGameSet.run_if(in_state(AppState::InGame)))
+        .configure_sets(Update, InGameSet.run_if(in_state(PauseState::Running)))
         .add_systems(
             Update,
             (
                 player_system,
                 guard_system,
+                guard_state_label_system.after(guard_system),
+                projectile_system,
+                dart_trap_system,
+                boss_system.after(player_system),
+                block_system.after(player_system),
+                pressure_plate_system.after(block_system),
                 key_pickup_system,
-                checkpoint_system,
+                upgrade_pickup_system,
+                checkpoint_system.before(respawn_system),
                 princess_rescue_system,
+                autosave_system
+                    .after(checkpoint_system)
+                    .after(princess_rescue_system),
                 hazard_system,
                 guard_hit_system,
-                respawn_system,
+                player_damage_system
+                    .after(player_system)
+                    .after(hazard_system)
+                    .after(guard_hit_system)
+                    .after(projectile_system)
+                    .after(boss_system),
+                respawn_system.after(player_damage_system),
+                restore_checkpoint_system.after(player_damage_system),
+                impact_feedback_system
+                    .after(player_damage_system)
+                    .after(guard_system),
+                hit_flash_system
+                    .after(player_system)
+                    .after(guard_system)
+                    .after(boss_system),
             )
                 .in_set(InGameSet),
         )
         .add_systems(
             Update,
             (
+                resume_save_system
+                    .before(respawn_system)
+                    .before(restore_checkpoint_system),
+                level_timer_system,
+                split_system
+                    .after(key_pickup_system)
+                    .after(checkpoint_system)
+                    .after(princess_rescue_system)
+                    .before(autosave_system),
+                run_timer_hud_system.after(split_system),
+                run_stats_system,
                 door_open_system,
+                gate_system,
                 fade_out_system,
+                particle_system,
+                air_hud_system.after(player_system),
+                health_hud_system.after(player_damage_system),
+                stealth_hud_system.after(guard_system),
+                dagger_hud_system.after(player_system),
+                boss_hud_system,
                 animate_key_system,
+                animate_blade_traps_system,
                 animate_princess_system,
                 animate_torches_system,
                 camera_follow_system,
@@ -348,6 +2063,8 @@ fn setup(
     mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
     mut images: ResMut<Assets<Image>>,
     render_mode: Res<RenderMode>,
+    archetypes: Res<EnemyArchetypes>,
+    bosses: Res<BossLibrary>,
 ) {
     if render_mode.is_cpu() {
         let target = create_low_res_target(&mut images);
@@ -425,9 +2142,29 @@ fn setup(
         ladder: asset_server.load("assets/ladder.png"),
         torch: asset_server.load("assets/torch.png"),
         glow: asset_server.load("assets/glow.png"),
-        player: asset_server.load("assets/player.png"),
-        guard: asset_server.load("assets/guard.png"),
+        player: asset_server.load("assets/player_sheet.png"),
+        enemies: archetypes
+            .archetypes
+            .iter()
+            .map(|(name, archetype)| (name.clone(), asset_server.load(archetype.sprite.clone())))
+            .collect(),
+        arrow: asset_server.load("assets/arrow.png"),
+        dart: asset_server.load("assets/dart.png"),
+        dart_trap: asset_server.load("assets/dart_trap.png"),
+        dagger: asset_server.load("assets/dagger.png"),
+        bosses: bosses
+            .bosses
+            .iter()
+            .map(|(name, boss)| (name.clone(), asset_server.load(boss.sprite.clone())))
+            .collect(),
+        orb: asset_server.load("assets/orb.png"),
         slash: asset_server.load("assets/slash.png"),
+        blade: asset_server.load("assets/blade.png"),
+        crate_block: asset_server.load("assets/crate.png"),
+        stone_block: asset_server.load("assets/stone_block.png"),
+        plate: asset_server.load("assets/plate.png"),
+        heart: asset_server.load("assets/heart.png"),
+        eye: asset_server.load("assets/eye.png"),
     };
 
     let audio = AudioAssets {
@@ -437,6 +2174,11 @@ fn setup(
         win: asset_server.load("assets/audio/win.wav"),
         alert: asset_server.load("assets/audio/alert.wav"),
         jump: asset_server.load("assets/audio/jump.wav"),
+        land: asset_server.load("assets/audio/land.wav"),
+        splash: asset_server.load("assets/audio/splash.wav"),
+        hurt: asset_server.load("assets/audio/hurt.wav"),
+        clang: asset_server.load("assets/audio/clang.wav"),
+        hit: asset_server.load("assets/audio/hit.wav"),
     };
 
     let ui_assets = UiAssets {
@@ -445,21 +2187,49 @@ fn setup(
 
     let player_layout = atlas_layouts.add(TextureAtlasLayout::from_grid(
         UVec2::new(24, 24),
-        2,
+        6,
         1,
         None,
         None,
     ));
-    let guard_layout = atlas_layouts.add(TextureAtlasLayout::from_grid(
-        UVec2::new(24, 24),
+    let enemy_layouts = archetypes
+        .archetypes
+        .iter()
+        .map(|(name, archetype)| {
+            let layout = atlas_layouts.add(TextureAtlasLayout::from_grid(
+                UVec2::new(archetype.frame_size.0, archetype.frame_size.1),
+                archetype.frames,
+                1,
+                None,
+                None,
+            ));
+            (name.clone(), layout)
+        })
+        .collect();
+    let boss_layouts = bosses
+        .bosses
+        .iter()
+        .map(|(name, boss)| {
+            let layout = atlas_layouts.add(TextureAtlasLayout::from_grid(
+                UVec2::new(boss.frame_size.0, boss.frame_size.1),
+                boss.frames,
+                1,
+                None,
+                None,
+            ));
+            (name.clone(), layout)
+        })
+        .collect();
+    let sofia_layout = atlas_layouts.add(TextureAtlasLayout::from_grid(
+        UVec2::new(24, 28),
         2,
         1,
         None,
         None,
     ));
-    let sofia_layout = atlas_layouts.add(TextureAtlasLayout::from_grid(
-        UVec2::new(24, 28),
-        2,
+    let eye_layout = atlas_layouts.add(TextureAtlasLayout::from_grid(
+        UVec2::new(12, 8),
+        3,
         1,
         None,
         None,
@@ -470,50 +2240,101 @@ fn setup(
     commands.insert_resource(ui_assets);
     commands.insert_resource(AtlasAssets {
         player: player_layout,
-        guard: guard_layout,
+        enemies: enemy_layouts,
+        bosses: boss_layouts,
         sofia: sofia_layout,
+        eye: eye_layout,
+    });
+
+    commands.insert_resource(LevelRules {
+        wall_jump: false,
+        tuning_preset: DEFAULT_TUNING_PRESET.to_string(),
     });
 
-    commands.insert_resource(GuardSpawns(vec![GuardSpawn {
-        pos: to_world(Vec2::new(620.0, 180.0)),
-        left: 540.0,
-        right: 700.0,
-        label: "guard1",
+    commands.insert_resource(GuardSpawns(vec![
+        GuardSpawn {
+            pos: to_world(Vec2::new(620.0, 180.0)),
+            left: 540.0,
+            right: 700.0,
+            leash: 80.0,
+            archetype: "guard",
+            label: "guard1",
+        },
+        GuardSpawn {
+            pos: to_world(Vec2::new(460.0, 180.0)),
+            left: 420.0,
+            right: 520.0,
+            leash: 60.0,
+            archetype: "armored",
+            label: "armored1",
+        },
+        GuardSpawn {
+            pos: to_world(Vec2::new(880.0, 110.0)),
+            left: 840.0,
+            right: 920.0,
+            leash: 0.0,
+            archetype: "archer",
+            label: "archer1",
+        },
+        GuardSpawn {
+            pos: to_world(Vec2::new(880.0, 180.0)),
+            left: 850.0,
+            right: 910.0,
+            leash: 60.0,
+            archetype: "skeleton",
+            label: "skeleton1",
+        },
+    ]));
+
+    commands.insert_resource(BossSpawns(vec![BossSpawn {
+        boss: "vizier",
+        pos: to_world(Vec2::new(1510.0, 170.0)),
+        arena_left: 1440.0,
+        arena_right: 1545.0,
+        entry_gate: to_world(Vec2::new(1428.0, 158.0)),
+        exit_gate: to_world(Vec2::new(1552.0, 158.0)),
+        label: "boss1",
     }]));
 }
 
 fn spawn_title_ui(
     mut commands: Commands,
     ui: Res<UiAssets>,
+    mut cursor: ResMut<MenuCursor>,
     existing: Query<Entity, With<TitleUi>>,
 ) {
     if !existing.is_empty() {
         return;
     }
-    commands.spawn((
-        Node {
-            width: percent(100),
-            height: percent(100),
-            display: Display::Flex,
-            justify_content: JustifyContent::Center,
-            align_items: AlignItems::Center,
-            ..default()
-        },
-        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
-        DespawnOnExit(AppState::Title),
-        TitleUi,
-        children![spawn_centered_text(
-            ui.font.clone(),
-            "PRINCE OF OLIVER\n\nOliver descends into the dungeon to rescue Princess Sofia.\nThe gates are locked, the shadows hide a guard...\n\nPress Space to start.",
-        )],
-    ));
+    cursor.selected = 0;
+    cursor.capturing = false;
+    let menu = spawn_menu(
+        &mut commands,
+        ui.font.clone(),
+        "PRINCE OF OLIVER\n\nOliver descends into the dungeon to rescue Princess Sofia.\nThe gates are locked, the shadows hide a guard...",
+        &[MenuAction::Continue, MenuAction::NewGame, MenuAction::SaveSlot],
+        Some(PromptText {
+            template: "{}/{} picks a slot. New game overwrites it.",
+            actions: &[Action::MoveLeft, Action::MoveRight],
+        }),
+        AppState::Title,
+    );
+    commands.entity(menu).insert(TitleUi);
 }
 
 fn spawn_end_ui(
     mut commands: Commands,
     ui: Res<UiAssets>,
+    actions: Res<ActionInput>,
+    settings: Res<Settings>,
+    results: RunResults,
     existing: Query<Entity, With<EndUi>>,
 ) {
+    let RunResults {
+        timer,
+        stats,
+        tuning,
+    } = results;
     if !existing.is_empty() {
         return;
     }
@@ -522,6 +2343,7 @@ fn spawn_end_ui(
             width: percent(100),
             height: percent(100),
             display: Display::Flex,
+            flex_direction: FlexDirection::Column,
             justify_content: JustifyContent::Center,
             align_items: AlignItems::Center,
             ..default()
@@ -529,18 +2351,30 @@ fn spawn_end_ui(
         BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
         DespawnOnExit(AppState::End),
         EndUi,
-        children![spawn_centered_text(
-            ui.font.clone(),
-            "Sofia is safe.\n\nMade for Oliver & Sofia.\n\nPress Space to return.",
-        )],
+        children![
+            spawn_centered_text(
+                ui.font.clone(),
+                "Sofia is safe.\n\nMade for Oliver & Sofia.\n\nPress {} to return.",
+                action_prompt(&actions, &settings, Action::Confirm),
+            ),
+            (
+                Text::new(results_text(&stats, &timer, &tuning)),
+                TextFont {
+                    font: ui.font.clone(),
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.85, 0.82, 0.75, 1.0)),
+                LineHeight::RelativeToFont(1.3),
+                TextLayout::new_with_justify(Justify::Center),
+            )
+        ],
     ));
 }
 
-fn spawn_centered_text(font: Handle<Font>, text: &str) -> impl Bundle {
+fn spawn_centered_text(font: Handle<Font>, template: &'static str, prompt: String) -> impl Bundle {
     (
         Node {
-            width: percent(100),
-            height: percent(100),
             padding: UiRect::all(px(24.0)),
             display: Display::Flex,
             justify_content: JustifyContent::Center,
@@ -548,7 +2382,7 @@ fn spawn_centered_text(font: Handle<Font>, text: &str) -> impl Bundle {
             ..default()
         },
         children![(
-            Text::new(text),
+            Text::new(template.replace("{}", &prompt)),
             TextFont {
                 font,
                 font_size: 32.0,
@@ -557,68 +2391,658 @@ fn spawn_centered_text(font: Handle<Font>, text: &str) -> impl Bundle {
             TextColor(Color::srgba(0.95, 0.92, 0.85, 1.0)),
             LineHeight::RelativeToFont(1.4),
             TextLayout::new_with_justify(Justify::Center),
+            PromptText {
+                template,
+                actions: &[Action::Confirm],
+            },
         )],
     )
 }
 
-fn title_input(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<AppState>>) {
-    if keys.just_pressed(KeyCode::Space) || keys.just_pressed(KeyCode::Enter) {
-        next_state.set(AppState::InGame);
-    }
-}
 
-fn end_input(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<AppState>>) {
-    if keys.just_pressed(KeyCode::Space) || keys.just_pressed(KeyCode::Enter) {
+fn end_input(actions: Res<ActionInput>, mut next_state: ResMut<NextState<AppState>>) {
+    if actions.just_pressed(Action::Confirm) {
         next_state.set(AppState::Title);
     }
 }
 
-fn spawn_level(
-    mut commands: Commands,
-    assets: Res<GameAssets>,
-    audio: Res<AudioAssets>,
-    ui: Res<UiAssets>,
-    atlases: Res<AtlasAssets>,
-    render_mode: Res<RenderMode>,
-    labels: Res<LabelSettings>,
-    mut session: ResMut<SessionState>,
-    guard_spawns: Res<GuardSpawns>,
+fn action_input_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    settings: Res<Settings>,
+    mut actions: ResMut<ActionInput>,
 ) {
-    session.has_key = false;
-    session.hud_key_icon = None;
+    let previous = std::mem::take(&mut actions.pressed);
+    for (action, bound) in settings.bindings.iter() {
+        if bound.iter().any(|key| keys.pressed(*key)) {
+            actions.pressed.insert(*action);
+        }
+    }
+    if keys.get_just_pressed().next().is_some() {
+        actions.device = InputDevice::Keyboard;
+    }
 
-    spawn_background(&mut commands, &assets, &render_mode);
-    spawn_platforms(&mut commands, &assets, &render_mode, &ui, &labels);
-    spawn_ladders(&mut commands, &assets, &ui, &labels);
-    spawn_spikes(&mut commands, &assets, &ui, &labels);
-    spawn_kill_zone(&mut commands, &ui, &labels);
-    spawn_key(&mut commands, &assets, &ui, &labels);
-    spawn_checkpoint(&mut commands, &ui, &labels);
-    spawn_door(&mut commands, &assets, &render_mode, &ui, &labels);
-    spawn_princess(&mut commands, &assets, &atlases, &render_mode, &ui, &labels);
-    spawn_player(&mut commands, &assets, &atlases, &render_mode, &ui, &labels);
-    spawn_guards(&mut commands, &assets, &atlases, &guard_spawns, &ui, &labels);
-    spawn_torches(&mut commands, &assets, &render_mode, &ui, &labels);
-    spawn_hud(&mut commands, &assets, &ui, &mut session);
+    for gamepad in gamepads.iter() {
+        for (action, bound) in settings.gamepad_bindings.iter() {
+            if bound.iter().any(|button| gamepad.pressed(*button)) {
+                actions.pressed.insert(*action);
+            }
+        }
+        let stick = gamepad.left_stick();
+        if stick.x < -GAMEPAD_DEADZONE {
+            actions.pressed.insert(Action::MoveLeft);
+        } else if stick.x > GAMEPAD_DEADZONE {
+            actions.pressed.insert(Action::MoveRight);
+        }
+        if stick.y > GAMEPAD_VERTICAL_THRESHOLD {
+            actions.pressed.insert(Action::Up);
+        } else if stick.y < -GAMEPAD_VERTICAL_THRESHOLD {
+            actions.pressed.insert(Action::Down);
+        }
+        if gamepad.get_just_pressed().next().is_some()
+            || stick.length() > GAMEPAD_VERTICAL_THRESHOLD
+        {
+            actions.device = InputDevice::Gamepad;
+        }
+    }
+    actions.just_pressed = actions.pressed.difference(&previous).copied().collect();
+}
 
-    commands.spawn((
-        AudioPlayer::new(audio.ambient.clone()),
-        PlaybackSettings {
-            volume: Volume::Linear(db_to_linear(-12.0)),
-            ..PlaybackSettings::LOOP
-        },
-        DespawnOnExit(AppState::InGame),
-    ));
+fn gamepad_connection_system(
+    mut reader: MessageReader<GamepadConnectionEvent>,
+    gamepads: Query<&Gamepad>,
+    mut actions: ResMut<ActionInput>,
+) {
+    for event in reader.read() {
+        if event.disconnected() && gamepads.is_empty() {
+            actions.device = InputDevice::Keyboard;
+        }
+    }
 }
 
-fn spawn_background(commands: &mut Commands, assets: &GameAssets, render_mode: &RenderMode) {
-    let center = Vec2::new(LEVEL_WIDTH * 0.5, LEVEL_HEIGHT * 0.5);
+fn prompt_text_system(
+    actions: Res<ActionInput>,
+    settings: Res<Settings>,
+    mut texts: Query<(&PromptText, &mut Text)>,
+) {
+    for (prompt, mut text) in texts.iter_mut() {
+        let mut label = prompt.template.to_string();
+        for action in prompt.actions {
+            label = label.replacen("{}", &action_prompt(&actions, &settings, *action), 1);
+        }
+        if text.0 != label {
+            text.0 = label;
+        }
+    }
+}
 
-    commands.spawn((
-        Sprite {
-            image: assets.background.clone(),
-            custom_size: Some(Vec2::new(LEVEL_WIDTH, LEVEL_HEIGHT)),
-            ..default()
+fn action_prompt(actions: &ActionInput, settings: &Settings, action: Action) -> String {
+    let prompt = match actions.device {
+        InputDevice::Keyboard => settings
+            .bindings
+            .get(&action)
+            .and_then(|keys| keys.first())
+            .map(|key| key_name(*key)),
+        InputDevice::Gamepad => settings
+            .gamepad_bindings
+            .get(&action)
+            .and_then(|buttons| buttons.first())
+            .map(|button| button_name(*button)),
+    };
+    prompt.unwrap_or_else(|| "?".to_string())
+}
+
+fn pause_input(
+    actions: Res<ActionInput>,
+    cursor: Res<MenuCursor>,
+    state: Res<State<PauseState>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    if !actions.just_pressed(Action::Pause) || cursor.capturing {
+        return;
+    }
+    next_state.set(match state.get() {
+        PauseState::Running => PauseState::Paused,
+        PauseState::Paused => PauseState::Running,
+        PauseState::Options => PauseState::Paused,
+        PauseState::Controls => PauseState::Options,
+    });
+}
+
+fn pause_game(mut time: ResMut<Time<Virtual>>, sinks: Query<&AudioSink>) {
+    time.pause();
+    for sink in sinks.iter() {
+        sink.pause();
+    }
+}
+
+fn resume_game(mut time: ResMut<Time<Virtual>>, sinks: Query<&AudioSink>) {
+    time.unpause();
+    for sink in sinks.iter() {
+        sink.play();
+    }
+}
+
+fn restart_level(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::InGame);
+}
+
+fn spawn_pause_menu(mut commands: Commands, ui: Res<UiAssets>, mut cursor: ResMut<MenuCursor>) {
+    cursor.selected = 0;
+    spawn_menu(
+        &mut commands,
+        ui.font.clone(),
+        "PAUSED",
+        &[
+            MenuAction::Resume,
+            MenuAction::RestartLevel,
+            MenuAction::RestartCheckpoint,
+            MenuAction::Options,
+            MenuAction::QuitToTitle,
+        ],
+        None,
+        PauseState::Paused,
+    );
+}
+
+fn spawn_options_menu(mut commands: Commands, ui: Res<UiAssets>, mut cursor: ResMut<MenuCursor>) {
+    cursor.selected = 0;
+    spawn_menu(
+        &mut commands,
+        ui.font.clone(),
+        "OPTIONS",
+        &[
+            MenuAction::MusicVolume,
+            MenuAction::SfxVolume,
+            MenuAction::Fullscreen,
+            MenuAction::WindowScale,
+            MenuAction::Renderer,
+            MenuAction::Backend,
+            MenuAction::Labels,
+            MenuAction::ScreenEffects,
+            MenuAction::Controls,
+            MenuAction::Back,
+        ],
+        Some(PromptText {
+            template: "{}/{} to change. * applies after restarting the game.",
+            actions: &[Action::MoveLeft, Action::MoveRight],
+        }),
+        PauseState::Options,
+    );
+}
+
+fn spawn_controls_menu(mut commands: Commands, ui: Res<UiAssets>, mut cursor: ResMut<MenuCursor>) {
+    cursor.selected = 0;
+    cursor.capturing = false;
+    let mut actions: Vec<MenuAction> = Action::ALL
+        .iter()
+        .map(|action| MenuAction::Bind(*action))
+        .collect();
+    actions.push(MenuAction::ResetBindings);
+    actions.push(MenuAction::Back);
+    spawn_menu(
+        &mut commands,
+        ui.font.clone(),
+        "CONTROLS",
+        &actions,
+        Some(PromptText {
+            template: "Enter adds a key, Backspace removes the last one.",
+            actions: &[],
+        }),
+        PauseState::Controls,
+    );
+}
+
+fn spawn_menu<S: States>(
+    commands: &mut Commands,
+    font: Handle<Font>,
+    title: &str,
+    actions: &[MenuAction],
+    hint: Option<PromptText>,
+    scope: S,
+) -> Entity {
+    commands
+        .spawn((
+            Node {
+                width: percent(100),
+                height: percent(100),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: px(10.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            GlobalZIndex(10),
+            DespawnOnExit(scope),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Node {
+                    margin: UiRect::bottom(px(16.0)),
+                    ..default()
+                },
+                Text::new(title),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 32.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.95, 0.92, 0.85, 1.0)),
+                TextLayout::new_with_justify(Justify::Center),
+            ));
+            for (index, action) in actions.iter().enumerate() {
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 24.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgba(0.7, 0.68, 0.62, 1.0)),
+                    MenuItem {
+                        action: *action,
+                        index,
+                    },
+                ));
+            }
+            if let Some(hint) = hint {
+                parent.spawn((
+                    Node {
+                        margin: UiRect::top(px(16.0)),
+                        ..default()
+                    },
+                    Text::new(""),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 18.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgba(0.7, 0.68, 0.62, 1.0)),
+                    hint,
+                ));
+            }
+        })
+        .id()
+}
+
+fn menu_system(
+    mut menu: MenuContext,
+    gamepads: Query<&Gamepad>,
+    mut items: Query<(&MenuItem, &mut Text, &mut TextColor)>,
+) {
+    let count = items.iter().count();
+    if count == 0 {
+        return;
+    }
+    let selected = items
+        .iter()
+        .find(|(item, _, _)| item.index == menu.cursor.selected)
+        .map(|(item, _, _)| item.action);
+
+    let button = gamepads
+        .iter()
+        .find_map(|gamepad| gamepad.get_just_pressed().next().copied());
+    if let (true, Some(button), Some(MenuAction::Bind(action))) =
+        (menu.cursor.capturing, button, selected)
+    {
+        menu.cursor.capturing = false;
+        let bound = menu.settings.gamepad_bindings.entry(action).or_default();
+        if !bound.contains(&button) {
+            bound.push(button);
+        }
+    } else if menu.cursor.capturing {
+        if let Some(key) = menu.keys.get_just_pressed().next().copied() {
+            menu.cursor.capturing = false;
+            if let (false, Some(MenuAction::Bind(action))) = (key == KeyCode::Escape, selected) {
+                let bound = menu.settings.bindings.entry(action).or_default();
+                if !bound.contains(&key) {
+                    bound.push(key);
+                }
+            }
+        }
+    } else {
+        if menu.actions.just_pressed(Action::Up) {
+            menu.cursor.selected = (menu.cursor.selected + count - 1) % count;
+        }
+        if menu.actions.just_pressed(Action::Down) {
+            menu.cursor.selected = (menu.cursor.selected + 1) % count;
+        }
+        let selected = items
+            .iter()
+            .find(|(item, _, _)| item.index == menu.cursor.selected)
+            .map(|(item, _, _)| item.action);
+        menu_confirm(&mut menu, selected);
+    }
+
+    for (item, mut text, mut color) in items.iter_mut() {
+        let selected = item.index == menu.cursor.selected;
+        let label = match (selected && menu.cursor.capturing, item.action) {
+            (true, MenuAction::Bind(action)) => {
+                format!("{}: press a key or button...", action.label())
+            }
+            _ => item.action.label(&menu.settings, &menu.saves),
+        };
+        let label = if selected { format!("> {} <", label) } else { label };
+        if text.0 != label {
+            text.0 = label;
+        }
+        color.0 = if selected {
+            Color::srgba(1.0, 0.85, 0.45, 1.0)
+        } else {
+            Color::srgba(0.7, 0.68, 0.62, 1.0)
+        };
+    }
+}
+
+fn menu_confirm(menu: &mut MenuContext, selected: Option<MenuAction>) {
+    let Some(action) = selected else {
+        return;
+    };
+    let confirm = menu.actions.just_pressed(Action::Confirm);
+    let step = if menu.actions.just_pressed(Action::MoveLeft) {
+        -1
+    } else if menu.actions.just_pressed(Action::MoveRight) || confirm {
+        1
+    } else {
+        0
+    };
+    if let (true, MenuAction::SaveSlot) = (step != 0, action) {
+        menu.saves.selected =
+            (menu.saves.selected as i32 + step).rem_euclid(SAVE_SLOTS as i32) as usize;
+    } else if step != 0 {
+        adjust_setting(&mut menu.settings, action, step);
+    }
+    if let (true, MenuAction::Bind(bound)) = (menu.keys.just_pressed(KeyCode::Backspace), action) {
+        if let Some(keys) = menu.settings.bindings.get_mut(&bound) {
+            if keys.len() > 1 {
+                keys.pop();
+            }
+        }
+    }
+    if !confirm {
+        return;
+    }
+    match action {
+        MenuAction::Continue => {
+            if let Some(data) = menu.saves.current().cloned() {
+                menu.session.wall_jump_unlocked = data.collected.contains(WALL_JUMP_ITEM);
+                menu.saves.resume = data.checkpoint.is_some();
+                menu.next_app_state.set(AppState::InGame);
+            }
+        }
+        MenuAction::NewGame => {
+            let data = SaveData {
+                version: SAVE_VERSION,
+                ..default()
+            };
+            let selected = menu.saves.selected;
+            write_save(selected, &data);
+            menu.saves.slots[selected] = SaveSlot {
+                data: Some(data),
+                error: None,
+            };
+            menu.saves.resume = false;
+            menu.session.wall_jump_unlocked = false;
+            menu.next_app_state.set(AppState::InGame);
+        }
+        MenuAction::Resume => menu.next_pause_state.set(PauseState::Running),
+        MenuAction::RestartLevel => menu.next_app_state.set(AppState::Restarting),
+        MenuAction::RestartCheckpoint => {
+            menu.respawn_writer.write(RespawnEvent { cause: None });
+            menu.next_pause_state.set(PauseState::Running);
+        }
+        MenuAction::Options => menu.next_pause_state.set(PauseState::Options),
+        MenuAction::QuitToTitle => menu.next_app_state.set(AppState::Title),
+        MenuAction::Controls => menu.next_pause_state.set(PauseState::Controls),
+        MenuAction::Bind(_) => menu.cursor.capturing = true,
+        MenuAction::ResetBindings => {
+            menu.settings.bindings = default_bindings();
+            menu.settings.gamepad_bindings = default_gamepad_bindings();
+        }
+        MenuAction::Back => {
+            let back = match menu.state.as_deref().map(State::get) {
+                Some(PauseState::Controls) => PauseState::Options,
+                _ => PauseState::Paused,
+            };
+            menu.next_pause_state.set(back);
+        }
+        _ => {}
+    }
+}
+
+fn default_bindings() -> BTreeMap<Action, Vec<KeyCode>> {
+    Action::ALL
+        .iter()
+        .map(|action| (*action, action.default_keys()))
+        .collect()
+}
+
+fn default_gamepad_bindings() -> BTreeMap<Action, Vec<GamepadButton>> {
+    Action::ALL
+        .iter()
+        .map(|action| (*action, action.default_buttons()))
+        .collect()
+}
+
+fn button_name(button: GamepadButton) -> String {
+    match button {
+        GamepadButton::South => "A".to_string(),
+        GamepadButton::East => "B".to_string(),
+        GamepadButton::West => "X".to_string(),
+        GamepadButton::North => "Y".to_string(),
+        GamepadButton::LeftTrigger => "LB".to_string(),
+        GamepadButton::RightTrigger => "RB".to_string(),
+        GamepadButton::LeftTrigger2 => "LT".to_string(),
+        GamepadButton::RightTrigger2 => "RT".to_string(),
+        GamepadButton::Select => "Back".to_string(),
+        GamepadButton::Start => "Start".to_string(),
+        GamepadButton::DPadUp => "D-pad up".to_string(),
+        GamepadButton::DPadDown => "D-pad down".to_string(),
+        GamepadButton::DPadLeft => "D-pad left".to_string(),
+        GamepadButton::DPadRight => "D-pad right".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+fn key_name(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    name.strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(&name)
+        .to_string()
+}
+
+fn adjust_setting(settings: &mut Settings, action: MenuAction, step: i32) {
+    let volume = |value: f32| ((value * 10.0).round() + step as f32).clamp(0.0, 10.0) / 10.0;
+    match action {
+        MenuAction::MusicVolume => settings.music_volume = volume(settings.music_volume),
+        MenuAction::SfxVolume => settings.sfx_volume = volume(settings.sfx_volume),
+        MenuAction::Fullscreen => settings.fullscreen = !settings.fullscreen,
+        MenuAction::WindowScale => {
+            let range = (MAX_WINDOW_SCALE - MIN_WINDOW_SCALE + 1) as i32;
+            let index = (settings.window_scale.saturating_sub(MIN_WINDOW_SCALE) as i32 + step)
+                .rem_euclid(range);
+            settings.window_scale = MIN_WINDOW_SCALE + index as u32;
+        }
+        MenuAction::Renderer => {
+            settings.render_mode = match settings.render_mode {
+                RenderMode::Cpu => RenderMode::Gpu,
+                RenderMode::Gpu => RenderMode::Cpu,
+            };
+        }
+        MenuAction::Backend => {
+            let backends = [RenderBackend::Auto, RenderBackend::Gl, RenderBackend::Vulkan];
+            let index = backends
+                .iter()
+                .position(|backend| *backend == settings.render_backend)
+                .unwrap_or(0) as i32;
+            settings.render_backend =
+                backends[(index + step).rem_euclid(backends.len() as i32) as usize];
+        }
+        MenuAction::Labels => settings.labels = !settings.labels,
+        MenuAction::ScreenEffects => settings.screen_effects = !settings.screen_effects,
+        _ => {}
+    }
+}
+
+fn apply_settings_system(
+    settings: Res<Settings>,
+    mut applied: Local<Option<Settings>>,
+    mut accessibility: ResMut<AccessibilitySettings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut music: Query<&mut AudioSink, With<Music>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let Some(previous) = applied.replace(settings.clone()) else {
+        return;
+    };
+    if previous == *settings {
+        return;
+    }
+
+    if previous.screen_effects != settings.screen_effects {
+        accessibility.screen_effects = settings.screen_effects;
+    }
+    if previous.music_volume != settings.music_volume {
+        for mut sink in music.iter_mut() {
+            sink.set_volume(music_volume(&settings));
+        }
+    }
+    if previous.fullscreen != settings.fullscreen || previous.window_scale != settings.window_scale
+    {
+        if let Ok(mut window) = windows.single_mut() {
+            window.mode = window_mode(&settings);
+            let (width, height) = window_resolution(&settings);
+            window.resolution.set(width as f32, height as f32);
+        }
+    }
+    save_settings(&settings);
+}
+
+fn music_volume(settings: &Settings) -> Volume {
+    Volume::Linear(db_to_linear(-12.0) * settings.music_volume)
+}
+
+fn window_resolution(settings: &Settings) -> (u32, u32) {
+    let scale = settings.window_scale.clamp(MIN_WINDOW_SCALE, MAX_WINDOW_SCALE);
+    (VIEW_WIDTH as u32 * scale, VIEW_HEIGHT as u32 * scale)
+}
+
+fn window_mode(settings: &Settings) -> WindowMode {
+    if settings.fullscreen {
+        WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+    } else {
+        WindowMode::Windowed
+    }
+}
+
+fn spawn_level(
+    mut commands: Commands,
+    level: LevelAssets,
+    data: LevelData,
+    audio: Res<AudioAssets>,
+    mut session: ResMut<SessionState>,
+    settings: Res<Settings>,
+) {
+    let LevelAssets {
+        assets,
+        atlases,
+        render_mode,
+        ui,
+        labels,
+    } = &level;
+    let LevelData {
+        guard_spawns,
+        archetypes,
+        boss_spawns,
+        bosses,
+        rules,
+        movement_tuning,
+    } = &data;
+    session.has_key = false;
+    session.hud_key_icon = None;
+    session.hud_air_bar = None;
+    session.hud_air_fill = None;
+    session.hud_hearts.clear();
+    session.hud_eye = None;
+    session.hud_daggers = None;
+    session.hud_boss_bar = None;
+    session.hud_boss_fill = None;
+    session.hud_boss_name = None;
+    commands.insert_resource(CheckpointSnapshot::default());
+    commands.insert_resource(LevelTimer::default());
+    session.hud_timer = None;
+    session.hud_split = None;
+    let wall_jump_enabled = rules.wall_jump || session.wall_jump_unlocked;
+    commands.insert_resource(RunStats {
+        collected: wall_jump_enabled
+            .then_some(WALL_JUMP_ITEM)
+            .into_iter()
+            .collect(),
+        ..default()
+    });
+    let preset = movement_tuning.preset(&rules.tuning_preset);
+
+    spawn_background(&mut commands, assets, render_mode);
+    spawn_platforms(&mut commands, &level);
+    spawn_ladders(&mut commands, assets, ui, labels);
+    spawn_water(&mut commands, assets, ui, labels);
+    spawn_spikes(&mut commands, assets, ui, labels);
+    spawn_blade_traps(&mut commands, assets, ui, labels);
+    spawn_dart_traps(&mut commands, assets, ui, labels);
+    spawn_blocks(&mut commands, assets, ui, labels);
+    spawn_plate_gates(&mut commands, assets, ui, labels);
+    spawn_kill_zone(&mut commands, ui, labels);
+    spawn_key(&mut commands, assets, ui, labels);
+    if !wall_jump_enabled {
+        spawn_wall_jump_upgrade(&mut commands, assets, ui, labels);
+    }
+    spawn_checkpoint(&mut commands, ui, labels);
+    spawn_door(&mut commands, assets, render_mode, ui, labels);
+    spawn_princess(&mut commands, assets, atlases, render_mode, ui, labels);
+    spawn_player(&mut commands, &level, &preset.player, wall_jump_enabled);
+    spawn_guards(
+        &mut commands,
+        &level,
+        guard_spawns,
+        archetypes,
+        &preset.guard,
+    );
+    spawn_bosses(
+        &mut commands,
+        assets,
+        atlases,
+        boss_spawns,
+        bosses,
+        ui,
+        labels,
+    );
+    spawn_torches(&mut commands, assets, render_mode, ui, labels);
+    spawn_hud(&mut commands, assets, atlases, ui, &mut session);
+
+    commands.spawn((
+        AudioPlayer::new(audio.ambient.clone()),
+        PlaybackSettings {
+            volume: music_volume(&settings),
+            ..PlaybackSettings::LOOP
+        },
+        Music,
+        DespawnOnExit(AppState::InGame),
+    ));
+}
+
+fn spawn_background(commands: &mut Commands, assets: &GameAssets, render_mode: &RenderMode) {
+    let center = Vec2::new(LEVEL_WIDTH * 0.5, LEVEL_HEIGHT * 0.5);
+
+    commands.spawn((
+        Sprite {
+            image: assets.background.clone(),
+            custom_size: Some(Vec2::new(LEVEL_WIDTH, LEVEL_HEIGHT)),
+            ..default()
         },
         Transform::from_xyz(center.x, center.y, Z_BG),
         DespawnOnExit(AppState::InGame),
@@ -649,15 +3073,12 @@ fn spawn_background(commands: &mut Commands, assets: &GameAssets, render_mode: &
     }
 }
 
-fn spawn_platforms(
-    commands: &mut Commands,
-    assets: &GameAssets,
-    render_mode: &RenderMode,
-    ui: &UiAssets,
-    labels: &LabelSettings,
-) {
+fn spawn_platforms(commands: &mut Commands, level: &LevelAssets) {
+    let LevelAssets {
+        assets, ui, labels, ..
+    } = level;
     let floors = [
-        ("floor1", Vec2::new(180.0, 210.0), Vec2::new(360.0, 24.0)),
+        ("floor1", Vec2::new(150.0, 210.0), Vec2::new(300.0, 24.0)),
         ("floor2", Vec2::new(600.0, 210.0), Vec2::new(400.0, 24.0)),
         ("floor3", Vec2::new(1010.0, 210.0), Vec2::new(340.0, 24.0)),
         ("floor4", Vec2::new(1390.0, 210.0), Vec2::new(420.0, 24.0)),
@@ -666,14 +3087,11 @@ fn spawn_platforms(
     for (name, pos, size) in floors {
         spawn_platform(
             commands,
-            assets,
+            level,
             name,
             to_world(pos),
             size,
             assets.floor.clone(),
-            render_mode,
-            ui,
-            labels,
         );
     }
 
@@ -689,29 +3107,43 @@ fn spawn_platforms(
     for (name, pos, size) in ledges {
         spawn_platform(
             commands,
-            assets,
+            level,
             name,
             to_world(pos),
             size,
             assets.ledge.clone(),
-            render_mode,
-            ui,
-            labels,
+        );
+    }
+
+    let tunnels = [("tunnel1", Vec2::new(1380.0, 91.0), Vec2::new(80.0, 182.0))];
+
+    for (name, pos, size) in tunnels {
+        spawn_platform(
+            commands,
+            level,
+            name,
+            to_world(pos),
+            size,
+            assets.wall.clone(),
         );
     }
 }
 
 fn spawn_platform(
     commands: &mut Commands,
-    assets: &GameAssets,
+    level: &LevelAssets,
     name: &str,
     pos: Vec2,
     size: Vec2,
     texture: Handle<Image>,
-    render_mode: &RenderMode,
-    ui: &UiAssets,
-    labels: &LabelSettings,
 ) {
+    let LevelAssets {
+        assets,
+        render_mode,
+        ui,
+        labels,
+        ..
+    } = level;
     let mut sprite = Sprite::from_image(texture);
     sprite.custom_size = Some(size);
 
@@ -864,6 +3296,63 @@ fn spawn_ladder(
     );
 }
 
+fn spawn_water(commands: &mut Commands, assets: &GameAssets, ui: &UiAssets, labels: &LabelSettings) {
+    let pools = [("water1", Vec2::new(350.0, 211.5), Vec2::new(100.0, 27.0))];
+
+    for (name, pos, size) in pools {
+        let pos = to_world(pos);
+        let entity = commands
+            .spawn((
+                Sprite {
+                    image: assets.pixel.clone(),
+                    custom_size: Some(size),
+                    color: Color::srgba(0.2, 0.4, 0.75, 0.45),
+                    ..default()
+                },
+                Transform::from_xyz(pos.x, pos.y, Z_WATER),
+                Collider { size },
+                Water {
+                    buoyancy: 470.0,
+                    drag: 3.0,
+                },
+                Name::new(name),
+                DespawnOnExit(AppState::InGame),
+            ))
+            .id();
+
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                Sprite {
+                    image: assets.pixel.clone(),
+                    custom_size: Some(Vec2::new(size.x, 1.0)),
+                    color: Color::srgba(0.7, 0.85, 1.0, 0.6),
+                    ..default()
+                },
+                Transform::from_xyz(0.0, size.y * 0.5 - 0.5, 0.1),
+            ));
+        });
+
+        commands.spawn((
+            Transform::from_xyz(pos.x, pos.y - size.y * 0.5 - 4.0, Z_PLATFORM),
+            Collider {
+                size: Vec2::new(size.x, 8.0),
+            },
+            Solid,
+            Name::new(format!("{}_basin", name)),
+            DespawnOnExit(AppState::InGame),
+        ));
+
+        maybe_attach_label(
+            commands,
+            ui,
+            labels,
+            entity,
+            name,
+            Vec2::new(0.0, size.y * 0.5 + 6.0),
+        );
+    }
+}
+
 fn spawn_spikes(commands: &mut Commands, assets: &GameAssets, ui: &UiAssets, labels: &LabelSettings) {
     let pos = to_world(Vec2::new(740.0, 198.0));
     let entity = commands
@@ -872,7 +3361,10 @@ fn spawn_spikes(commands: &mut Commands, assets: &GameAssets, ui: &UiAssets, lab
             Collider {
                 size: Vec2::new(32.0, 14.0),
             },
-            Hazard,
+            Hazard {
+                damage: Damage::Kill,
+                cause: DeathCause::Spikes,
+            },
             Name::new("spikes1"),
             DespawnOnExit(AppState::InGame),
         ))
@@ -899,29 +3391,212 @@ fn spawn_spikes(commands: &mut Commands, assets: &GameAssets, ui: &UiAssets, lab
     );
 }
 
-fn spawn_kill_zone(commands: &mut Commands, ui: &UiAssets, labels: &LabelSettings) {
-    let pos = to_world(Vec2::new(LEVEL_WIDTH * 0.5, LEVEL_HEIGHT + 40.0));
-    let entity = commands
-        .spawn((
-        Transform::from_xyz(pos.x, pos.y, Z_INTERACT),
-        Collider {
-            size: Vec2::new(LEVEL_WIDTH, 80.0),
-        },
-        Hazard,
-        Name::new("kill_zone1"),
-        DespawnOnExit(AppState::InGame),
-    ))
-    .id();
+fn spawn_dart_traps(commands: &mut Commands, assets: &GameAssets, ui: &UiAssets, labels: &LabelSettings) {
+    let traps = [("dart_trap1", Vec2::new(1337.0, 178.0), -1.0, 2.2)];
 
-    maybe_attach_label(
-        commands,
-        ui,
-        labels,
-        entity,
-        "kill_zone1",
-        Vec2::new(0.0, 50.0),
-    );
-}
+    for (index, (name, pos, direction, interval)) in traps.iter().enumerate() {
+        let pos = to_world(*pos);
+        let entity = commands
+            .spawn((
+                Sprite {
+                    image: assets.dart_trap.clone(),
+                    flip_x: *direction > 0.0,
+                    ..default()
+                },
+                Transform::from_xyz(pos.x, pos.y, Z_INTERACT),
+                DartTrap {
+                    direction: *direction,
+                    interval: *interval,
+                    timer: *interval * (0.5 + index as f32 * 0.3),
+                    speed: 140.0,
+                },
+                Name::new(*name),
+                DespawnOnExit(AppState::InGame),
+            ))
+            .id();
+
+        maybe_attach_label(
+            commands,
+            ui,
+            labels,
+            entity,
+            name,
+            Vec2::new(0.0, 10.0),
+        );
+    }
+}
+
+fn spawn_blade_traps(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    ui: &UiAssets,
+    labels: &LabelSettings,
+) {
+    let blades = [("blade1", Vec2::new(1290.0, 178.0), 10.0)];
+
+    for (index, (name, pos, range)) in blades.iter().enumerate() {
+        let pos = to_world(*pos);
+        let entity = commands
+            .spawn((
+                Sprite::from_image(assets.blade.clone()),
+                Transform::from_xyz(pos.x, pos.y, Z_INTERACT),
+                Collider {
+                    size: Vec2::new(22.0, 5.0),
+                },
+                Hazard {
+                    damage: Damage::Hit(1),
+                    cause: DeathCause::Trap,
+                },
+                BladeTrap {
+                    base_x: pos.x,
+                    range: *range,
+                    phase: index as f32 * 1.3,
+                },
+                Name::new(*name),
+                DespawnOnExit(AppState::InGame),
+            ))
+            .id();
+
+        maybe_attach_label(
+            commands,
+            ui,
+            labels,
+            entity,
+            name,
+            Vec2::new(0.0, 10.0),
+        );
+    }
+}
+
+fn spawn_blocks(commands: &mut Commands, assets: &GameAssets, ui: &UiAssets, labels: &LabelSettings) {
+    let blocks = [
+        ("crate1", Vec2::new(930.0, 190.0), assets.crate_block.clone(), 40.0, 30.0),
+        ("stone1", Vec2::new(1070.0, 190.0), assets.stone_block.clone(), 20.0, 15.0),
+    ];
+
+    for (name, pos, image, push_speed, pull_speed) in blocks {
+        let pos = to_world(pos);
+        let entity = commands
+            .spawn((
+                Sprite::from_image(image),
+                Transform::from_xyz(pos.x, pos.y, Z_PLATFORM + 0.5),
+                Collider {
+                    size: Vec2::new(16.0, 16.0),
+                },
+                Velocity(Vec2::ZERO),
+                Solid,
+                Block {
+                    push_speed,
+                    pull_speed,
+                    home: pos,
+                },
+                Name::new(name),
+                DespawnOnExit(AppState::InGame),
+            ))
+            .id();
+
+        maybe_attach_label(
+            commands,
+            ui,
+            labels,
+            entity,
+            name,
+            Vec2::new(0.0, 14.0),
+        );
+    }
+}
+
+fn spawn_plate_gates(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    ui: &UiAssets,
+    labels: &LabelSettings,
+) {
+    let gate_pos = to_world(Vec2::new(1160.0, 148.0));
+    let gate_size = Vec2::new(12.0, 100.0);
+    let gate = commands
+        .spawn((
+            Sprite {
+                image: assets.wall.clone(),
+                custom_size: Some(gate_size),
+                color: Color::srgba(0.6, 0.55, 0.5, 1.0),
+                ..default()
+            },
+            Transform::from_xyz(gate_pos.x, gate_pos.y, Z_PLATFORM),
+            Collider { size: gate_size },
+            Solid,
+            Gate {
+                closed_y: gate_pos.y,
+                open_y: gate_pos.y + gate_size.y,
+                open: false,
+            },
+            Name::new("gate1"),
+            DespawnOnExit(AppState::InGame),
+        ))
+        .id();
+
+    maybe_attach_label(
+        commands,
+        ui,
+        labels,
+        gate,
+        "gate1",
+        Vec2::new(0.0, gate_size.y * 0.5 + 6.0),
+    );
+
+    let plate_pos = to_world(Vec2::new(1110.0, 196.5));
+    let plate = commands
+        .spawn((
+            Sprite::from_image(assets.plate.clone()),
+            Transform::from_xyz(plate_pos.x, plate_pos.y, Z_INTERACT),
+            Collider {
+                size: Vec2::new(16.0, 3.0),
+            },
+            PressurePlate {
+                gate,
+                pressed: false,
+            },
+            Name::new("plate1"),
+            DespawnOnExit(AppState::InGame),
+        ))
+        .id();
+
+    maybe_attach_label(
+        commands,
+        ui,
+        labels,
+        plate,
+        "plate1",
+        Vec2::new(0.0, 10.0),
+    );
+}
+
+fn spawn_kill_zone(commands: &mut Commands, ui: &UiAssets, labels: &LabelSettings) {
+    let pos = to_world(Vec2::new(LEVEL_WIDTH * 0.5, LEVEL_HEIGHT + 40.0));
+    let entity = commands
+        .spawn((
+        Transform::from_xyz(pos.x, pos.y, Z_INTERACT),
+        Collider {
+            size: Vec2::new(LEVEL_WIDTH, 80.0),
+        },
+        Hazard {
+            damage: Damage::Kill,
+            cause: DeathCause::KillZone,
+        },
+        Name::new("kill_zone1"),
+        DespawnOnExit(AppState::InGame),
+    ))
+    .id();
+
+    maybe_attach_label(
+        commands,
+        ui,
+        labels,
+        entity,
+        "kill_zone1",
+        Vec2::new(0.0, 50.0),
+    );
+}
 
 fn spawn_key(commands: &mut Commands, assets: &GameAssets, ui: &UiAssets, labels: &LabelSettings) {
     let pos = to_world(Vec2::new(880.0, 126.0));
@@ -952,6 +3627,45 @@ fn spawn_key(commands: &mut Commands, assets: &GameAssets, ui: &UiAssets, labels
     );
 }
 
+fn spawn_wall_jump_upgrade(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    ui: &UiAssets,
+    labels: &LabelSettings,
+) {
+    let pos = to_world(Vec2::new(360.0, 132.0));
+    let entity = commands
+        .spawn((
+            Sprite {
+                image: assets.glow.clone(),
+                color: Color::srgba(0.55, 0.85, 1.0, 0.9),
+                custom_size: Some(Vec2::new(14.0, 14.0)),
+                ..default()
+            },
+            Transform::from_xyz(pos.x, pos.y, Z_INTERACT),
+            Collider {
+                size: Vec2::new(12.0, 12.0),
+            },
+            WallJumpUpgrade,
+            Name::new("upgrade_wall_jump1"),
+            KeyFloat {
+                base_y: pos.y,
+                time: 0.0,
+            },
+            DespawnOnExit(AppState::InGame),
+        ))
+        .id();
+
+    maybe_attach_label(
+        commands,
+        ui,
+        labels,
+        entity,
+        "upgrade_wall_jump1",
+        Vec2::new(0.0, 14.0),
+    );
+}
+
 fn spawn_checkpoint(commands: &mut Commands, ui: &UiAssets, labels: &LabelSettings) {
     let pos = to_world(Vec2::new(990.0, 190.0));
     let entity = commands
@@ -960,7 +3674,7 @@ fn spawn_checkpoint(commands: &mut Commands, ui: &UiAssets, labels: &LabelSettin
         Collider {
             size: Vec2::new(20.0, 20.0),
         },
-        Checkpoint,
+        Checkpoint { occupied: false },
         Name::new("checkpoint1"),
         DespawnOnExit(AppState::InGame),
     ))
@@ -976,6 +3690,19 @@ fn spawn_checkpoint(commands: &mut Commands, ui: &UiAssets, labels: &LabelSettin
     );
 }
 
+fn spawn_door_blocker(commands: &mut Commands, pos: Vec2) {
+    commands.spawn((
+        Transform::from_xyz(pos.x, pos.y, Z_INTERACT),
+        Collider {
+            size: Vec2::new(24.0, 60.0),
+        },
+        Solid,
+        DoorBlocker,
+        Name::new("door_blocker1"),
+        DespawnOnExit(AppState::InGame),
+    ));
+}
+
 fn spawn_door(
     commands: &mut Commands,
     assets: &GameAssets,
@@ -992,22 +3719,13 @@ fn spawn_door(
             ..default()
         },
         Transform::from_xyz(pos.x, pos.y, Z_INTERACT),
-        Door,
+        Door { closed_y: pos.y },
         Name::new("door1"),
         DespawnOnExit(AppState::InGame),
     ))
     .id();
 
-    commands.spawn((
-        Transform::from_xyz(pos.x, pos.y, Z_INTERACT),
-        Collider {
-            size: Vec2::new(24.0, 60.0),
-        },
-        Solid,
-        DoorBlocker,
-        Name::new("door_blocker1"),
-        DespawnOnExit(AppState::InGame),
-    ));
+    spawn_door_blocker(commands, pos);
 
     if !render_mode.is_cpu() {
         let lintel_pos = to_world(Vec2::new(1230.0, 130.0));
@@ -1041,7 +3759,7 @@ fn spawn_princess(
     ui: &UiAssets,
     labels: &LabelSettings,
 ) {
-    let pos = to_world(Vec2::new(1500.0, 181.0));
+    let pos = to_world(Vec2::new(1580.0, 181.0));
     let entity = commands
         .spawn((
             Transform::from_xyz(pos.x, pos.y, Z_INTERACT),
@@ -1096,12 +3814,17 @@ fn spawn_princess(
 
 fn spawn_player(
     commands: &mut Commands,
-    assets: &GameAssets,
-    atlases: &AtlasAssets,
-    render_mode: &RenderMode,
-    ui: &UiAssets,
-    labels: &LabelSettings,
+    level: &LevelAssets,
+    tuning: &PlayerTuning,
+    wall_jump_enabled: bool,
 ) {
+    let LevelAssets {
+        assets,
+        atlases,
+        render_mode,
+        ui,
+        labels,
+    } = level;
     let pos = to_world(Vec2::new(80.0, 180.0));
     let player_entity = commands
         .spawn((
@@ -1117,6 +3840,10 @@ fn spawn_player(
                 size: Vec2::new(14.0, 24.0),
             },
             Velocity(Vec2::ZERO),
+            Health {
+                current: PLAYER_MAX_HEALTH,
+                max: PLAYER_MAX_HEALTH,
+            },
             Player,
             Name::new("player1"),
             DespawnOnExit(AppState::InGame),
@@ -1154,23 +3881,68 @@ fn spawn_player(
             .id();
     });
 
-    commands.entity(player_entity).insert(PlayerState {
-        speed: 90.0,
-        jump_velocity: 190.0,
-        gravity: -520.0,
-        coyote_time: 0.12,
-        jump_buffer: 0.12,
-        climb_speed: 60.0,
-        coyote_timer: 0.12,
+    let mut state = PlayerState {
+        speed: 0.0,
+        jump_velocity: 0.0,
+        gravity: 0.0,
+        jump_cut: 0.0,
+        fall_gravity_scale: 0.0,
+        apex_gravity_scale: 0.0,
+        apex_threshold: 0.0,
+        max_fall_speed: 0.0,
+        swim_speed: 0.0,
+        swim_stroke: 0.0,
+        air_time: 0.0,
+        air: 0.0,
+        in_water: false,
+        coyote_time: 0.0,
+        jump_buffer: 0.0,
+        climb_speed: 0.0,
+        coyote_timer: 0.0,
         jump_buffer_timer: 0.0,
+        jump_held: false,
+        grounded: false,
+        fall_apex_y: pos.y,
+        land_stun_timer: 0.0,
+        stand_height: 24.0,
+        crouch_height: 14.0,
+        crouched: false,
+        pushing: false,
+        pulling: false,
+        push_speed: 0.0,
+        hurt_timer: 0.0,
+        invincible_timer: 0.0,
+        invincible_time: 1.2,
+        knockback: Vec2::new(120.0, 120.0),
         on_ladder: false,
+        wall_jump_enabled,
+        wall_slide_speed: 45.0,
+        wall_jump_velocity: Vec2::new(110.0, 180.0),
+        wall_jump_lock: 0.18,
+        wall_jump_lock_timer: 0.0,
+        wall_dir: 0.0,
         respawn_position: pos,
         walk_timer: 0.0,
-        attack_cooldown: 0.0,
-        attack_active: 0.0,
+        attack: None,
+        attack_timer: 0.0,
+        attack_windup: 0.0,
+        attack_connected: false,
+        attack_hitbox: None,
+        attack_buffer: 0.0,
+        combo_step: 0,
+        combo_timer: 0.0,
+        daggers: MAX_DAGGERS,
+        parry_timer: 0.0,
+        parry_cooldown: 0.0,
+        stagger_timer: 0.0,
+        duel_speed: 40.0,
         facing: 1.0,
         slash_entity,
-    });
+    };
+    apply_player_tuning(&mut state, tuning);
+    state.coyote_timer = state.coyote_time;
+    state.air = state.air_time;
+    commands.entity(player_entity).insert(state);
 
     maybe_attach_label(
         commands,
@@ -1185,35 +3957,79 @@ fn spawn_player(
 
 fn spawn_guards(
     commands: &mut Commands,
-    assets: &GameAssets,
-    atlases: &AtlasAssets,
+    level: &LevelAssets,
     guard_spawns: &GuardSpawns,
-    ui: &UiAssets,
-    labels: &LabelSettings,
+    archetypes: &EnemyArchetypes,
+    tuning: &GuardTuning,
 ) {
-    for spawn in guard_spawns.0.iter().copied() {
+    let LevelAssets {
+        assets,
+        atlases,
+        ui,
+        labels,
+        ..
+    } = level;
+    for (index, spawn) in guard_spawns.0.iter().copied().enumerate() {
+        let archetype = archetypes.archetype(spawn.archetype);
+        let (Some(image), Some(layout)) = (
+            assets.enemies.get(spawn.archetype),
+            atlases.enemies.get(spawn.archetype),
+        ) else {
+            warn!("No sprite loaded for enemy archetype '{}'", spawn.archetype);
+            continue;
+        };
         let entity = commands
             .spawn((
             Sprite::from_atlas_image(
-                assets.guard.clone(),
+                image.clone(),
                 TextureAtlas {
-                    layout: atlases.guard.clone(),
+                    layout: layout.clone(),
                     index: 0,
                 },
             ),
             Transform::from_xyz(spawn.pos.x, spawn.pos.y, Z_ACTOR - 1.0),
             Collider {
-                size: Vec2::new(14.0, 24.0),
+                size: Vec2::new(archetype.collider.0, archetype.collider.1),
             },
             Velocity(Vec2::ZERO),
             Guard {
-                speed: 40.0,
-                gravity: -520.0,
+                speed: tuning.speed,
+                speed_scale: archetype.speed_scale,
+                gravity: tuning.gravity,
+                climb_speed: tuning.climb_speed,
+                climb_dir: 0.0,
+                home_y: spawn.pos.y,
                 left_limit: spawn.left,
                 right_limit: spawn.right,
+                leash: spawn.leash,
                 direction: 1.0,
                 walk_timer: 0.0,
                 alive: true,
+                state: GuardState::Patrol,
+                state_timer: 0.0,
+                lost_timer: 0.0,
+                awareness: 0.0,
+                duel_speed: archetype.duel_speed,
+                windup: archetype.windup,
+                windup_timer: 0.0,
+                strike_cooldown: 0.0,
+                parry_chance: archetype.parry_chance,
+                parry_timer: 0.0,
+                stagger_timer: 0.0,
+                knockback: archetype.knockback,
+                backstab: archetype.backstab,
+                ranged: archetype.ranged,
+                fire_cooldown: 0.0,
+                revive_time: archetype.revive_time,
+                downed_timer: 0.0,
+                aim_frame: archetype.aim_frame,
+                down_frame: archetype.down_frame,
+                reacted: false,
+                rng: 0x9E37_79B9 ^ (index as u32 + 1).wrapping_mul(0x85EB_CA6B),
+            },
+            Health {
+                current: archetype.health,
+                max: archetype.health,
             },
             Name::new(spawn.label),
             DespawnOnExit(AppState::InGame),
@@ -1228,6 +4044,127 @@ fn spawn_guards(
             spawn.label,
             Vec2::new(0.0, 20.0),
         );
+        if labels.enabled {
+            commands.entity(entity).with_children(|parent| {
+                parent.spawn((
+                    Text2d::new(GuardState::Patrol.label()),
+                    TextFont {
+                        font: ui.font.clone(),
+                        font_size: 10.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgba(1.0, 0.75, 0.55, 1.0)),
+                    TextLayout::new_with_justify(Justify::Center),
+                    Transform::from_xyz(0.0, 30.0, Z_LABEL),
+                    GuardStateLabel,
+                ));
+            });
+        }
+    }
+}
+
+fn spawn_bosses(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    atlases: &AtlasAssets,
+    boss_spawns: &BossSpawns,
+    bosses: &BossLibrary,
+    ui: &UiAssets,
+    labels: &LabelSettings,
+) {
+    for spawn in boss_spawns.0.iter().copied() {
+        let (Some(def), Some(image), Some(layout)) = (
+            bosses.bosses.get(spawn.boss),
+            assets.bosses.get(spawn.boss),
+            atlases.bosses.get(spawn.boss),
+        ) else {
+            warn!("Unknown boss '{}', skipping", spawn.boss);
+            continue;
+        };
+        let boss = commands
+            .spawn((
+                Sprite::from_atlas_image(
+                    image.clone(),
+                    TextureAtlas {
+                        layout: layout.clone(),
+                        index: 0,
+                    },
+                ),
+                Transform::from_xyz(spawn.pos.x, spawn.pos.y, Z_ACTOR - 1.0),
+                Collider {
+                    size: Vec2::new(def.collider.0, def.collider.1),
+                },
+                Velocity(Vec2::ZERO),
+                Health {
+                    current: def.health,
+                    max: def.health,
+                },
+                Boss {
+                    name: def.name.clone(),
+                    phases: def.phases.clone(),
+                    phase: 0,
+                    step: 0,
+                    timer: 0.0,
+                    done: false,
+                    active: false,
+                    defeated: false,
+                    arena_left: spawn.arena_left,
+                    arena_right: spawn.arena_right,
+                    direction: -1.0,
+                    stagger_timer: 0.0,
+                    hit_timer: 0.0,
+                },
+                Name::new(spawn.label),
+                DespawnOnExit(AppState::InGame),
+            ))
+            .id();
+
+        maybe_attach_label(
+            commands,
+            ui,
+            labels,
+            boss,
+            spawn.label,
+            Vec2::new(0.0, def.collider.1 * 0.5 + 8.0),
+        );
+
+        for (gate_name, gate_pos, exit) in [
+            ("arena_gate1", spawn.entry_gate, false),
+            ("arena_gate2", spawn.exit_gate, true),
+        ] {
+            let gate_size = Vec2::new(10.0, 80.0);
+            let open_y = gate_pos.y + gate_size.y;
+            let gate = commands
+                .spawn((
+                    Sprite {
+                        image: assets.wall.clone(),
+                        custom_size: Some(gate_size),
+                        color: Color::srgba(0.55, 0.45, 0.5, 1.0),
+                        ..default()
+                    },
+                    Transform::from_xyz(gate_pos.x, if exit { gate_pos.y } else { open_y }, Z_PLATFORM),
+                    Collider { size: gate_size },
+                    Solid,
+                    Gate {
+                        closed_y: gate_pos.y,
+                        open_y,
+                        open: !exit,
+                    },
+                    ArenaGate { boss },
+                    Name::new(gate_name),
+                    DespawnOnExit(AppState::InGame),
+                ))
+                .id();
+
+            maybe_attach_label(
+                commands,
+                ui,
+                labels,
+                gate,
+                gate_name,
+                Vec2::new(0.0, gate_size.y * 0.5 + 6.0),
+            );
+        }
     }
 }
 
@@ -1251,6 +4188,9 @@ fn spawn_torches(
             .spawn((
                 Sprite::from_image(assets.torch.clone()),
                 Transform::from_xyz(pos.x, pos.y, Z_INTERACT),
+                LightSource {
+                    radius: TORCH_LIGHT_RADIUS,
+                },
                 Name::new(*name),
                 DespawnOnExit(AppState::InGame),
             ))
@@ -1291,6 +4231,7 @@ fn spawn_torches(
 fn spawn_hud(
     commands: &mut Commands,
     assets: &GameAssets,
+    atlases: &AtlasAssets,
     ui: &UiAssets,
     session: &mut SessionState,
 ) {
@@ -1311,6 +4252,20 @@ fn spawn_hud(
         .id();
 
     commands.entity(root).with_children(|parent| {
+        for _ in 0..PLAYER_MAX_HEALTH {
+            let heart = parent
+                .spawn((
+                    ImageNode::new(assets.heart.clone()),
+                    Node {
+                        width: px(9.0),
+                        height: px(8.0),
+                        ..default()
+                    },
+                ))
+                .id();
+            session.hud_hearts.push(heart);
+        }
+
         let icon = parent
             .spawn((
                 ImageNode::new(assets.key.clone()).with_color(Color::srgba(0.5, 0.5, 0.5, 0.8)),
@@ -1332,86 +4287,403 @@ fn spawn_hud(
             },
             TextColor(Color::srgba(0.9, 0.85, 0.75, 0.9)),
         ));
-    });
-}
 
-fn player_system(
-    time: Res<Time>,
-    keys: Res<ButtonInput<KeyCode>>,
-    audio: Res<AudioAssets>,
-    mut commands: Commands,
-    solids: Query<(&Transform, &Collider), (With<Solid>, Without<Player>)>,
-    ladders: Query<(&Transform, &Collider), (With<Ladder>, Without<Player>)>,
-    mut player_q: Query<
-        (&mut Transform, &mut Sprite, &mut PlayerState, &mut Velocity, &Collider),
-        With<Player>,
-    >,
-    mut guards: Query<
-        (Entity, &Transform, &mut Guard, &Collider, &mut Velocity),
-        (With<Guard>, Without<Player>),
-    >,
-    mut slash_q: Query<
-        (&mut Transform, &mut Sprite, &mut Visibility),
-        (With<Slash>, Without<Player>, Without<Guard>, Without<Solid>, Without<Ladder>),
-    >,
-) {
-    let dt = time.delta_secs();
-    if let Ok((mut transform, mut sprite, mut state, mut velocity, collider)) =
-        player_q.single_mut()
-    {
-        let pos = Vec2::new(transform.translation.x, transform.translation.y);
-        let set_respawn = keys.just_pressed(KeyCode::KeyR);
-        let ladder_hit = ladders.iter().any(|(ladder_tf, ladder_collider)| {
-            aabb_intersects(
-                pos,
-                collider.size,
-                Vec2::new(ladder_tf.translation.x, ladder_tf.translation.y),
-                ladder_collider.size,
-            )
-        });
-        state.on_ladder = ladder_hit;
-
-        state.attack_cooldown = (state.attack_cooldown - dt).max(0.0);
-        if state.attack_active > 0.0 {
-            state.attack_active = (state.attack_active - dt).max(0.0);
-            if state.attack_active == 0.0 {
-                if let Ok((_, _, mut visibility)) = slash_q.get_mut(state.slash_entity) {
-                    *visibility = Visibility::Hidden;
-                }
-            }
-        }
+        let air_bar = parent
+            .spawn((
+                Node {
+                    width: px(40.0),
+                    height: px(5.0),
+                    margin: UiRect::left(px(6.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.05, 0.1, 0.2, 0.8)),
+                Visibility::Hidden,
+            ))
+            .with_children(|bar| {
+                let fill = bar
+                    .spawn((
+                        Node {
+                            width: percent(100),
+                            height: percent(100),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgba(0.55, 0.8, 1.0, 0.9)),
+                    ))
+                    .id();
+                session.hud_air_fill = Some(fill);
+            })
+            .id();
+        session.hud_air_bar = Some(air_bar);
 
-        let on_ground = state.coyote_timer > 0.0;
-        let input_dir = move_input(&keys);
-        let crouching =
-            (keys.pressed(KeyCode::ArrowDown) || keys.pressed(KeyCode::KeyS)) && on_ground;
-        let mut max_speed = state.speed;
-        if crouching {
-            max_speed *= 0.4;
-        }
+        let eye = parent
+            .spawn((
+                ImageNode::from_atlas_image(
+                    assets.eye.clone(),
+                    TextureAtlas {
+                        layout: atlases.eye.clone(),
+                        index: 0,
+                    },
+                ),
+                Node {
+                    width: px(12.0),
+                    height: px(8.0),
+                    margin: UiRect::left(px(6.0)),
+                    ..default()
+                },
+            ))
+            .id();
+        session.hud_eye = Some(eye);
+
+        parent.spawn((
+            ImageNode::new(assets.dagger.clone()),
+            Node {
+                width: px(9.0),
+                height: px(3.0),
+                margin: UiRect::left(px(6.0)),
+                ..default()
+            },
+        ));
+        let daggers = parent
+            .spawn((
+                Text::new(format!("x{}", MAX_DAGGERS)),
+                TextFont {
+                    font: ui.font.clone(),
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.9, 0.85, 0.75, 0.9)),
+            ))
+            .id();
+        session.hud_daggers = Some(daggers);
+    });
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: px(8.0),
+                top: px(8.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexEnd,
+                ..default()
+            },
+            DespawnOnExit(AppState::InGame),
+        ))
+        .with_children(|parent| {
+            let timer = parent
+                .spawn((
+                    Text::new(format_time(0.0)),
+                    TextFont {
+                        font: ui.font.clone(),
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgba(0.9, 0.85, 0.75, 0.9)),
+                ))
+                .id();
+            session.hud_timer = Some(timer);
+            let split = parent
+                .spawn((
+                    Text::new(""),
+                    TextFont {
+                        font: ui.font.clone(),
+                        font_size: 12.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgba(0.9, 0.85, 0.75, 0.9)),
+                ))
+                .id();
+            session.hud_split = Some(split);
+        });
 
-        if keys.just_pressed(KeyCode::KeyE) && state.attack_cooldown == 0.0 {
-            state.attack_cooldown = 0.35;
-            state.attack_active = 0.18;
-            if let Ok((_, _, mut visibility)) = slash_q.get_mut(state.slash_entity) {
-                *visibility = Visibility::Visible;
+    let boss_bar = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: percent(25),
+                width: percent(50),
+                bottom: px(10.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: px(3.0),
+                ..default()
+            },
+            Visibility::Hidden,
+            DespawnOnExit(AppState::InGame),
+        ))
+        .with_children(|parent| {
+            let name = parent
+                .spawn((
+                    Text::new(""),
+                    TextFont {
+                        font: ui.font.clone(),
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgba(0.95, 0.85, 0.7, 0.95)),
+                ))
+                .id();
+            session.hud_boss_name = Some(name);
+            parent
+                .spawn((
+                    Node {
+                        width: percent(100),
+                        height: px(6.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.15, 0.05, 0.08, 0.85)),
+                ))
+                .with_children(|bar| {
+                    let fill = bar
+                        .spawn((
+                            Node {
+                                width: percent(100),
+                                height: percent(100),
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgba(0.75, 0.15, 0.25, 0.95)),
+                        ))
+                        .id();
+                    session.hud_boss_fill = Some(fill);
+                });
+        })
+        .id();
+    session.hud_boss_bar = Some(boss_bar);
+}
+
+fn player_system(
+    ctx: ActorContext,
+    params: PlayerParams,
+    events: CombatEvents,
+    mut commands: Commands,
+    world: PlayerWorld,
+    mut player_q: PlayerBody,
+    mut guards: GuardTargets<(With<Guard>, Without<Player>)>,
+) {
+    let ActorContext {
+        time,
+        assets,
+        audio,
+    } = ctx;
+    let PlayerParams {
+        actions,
+        fall,
+        attacks,
+        mut noise_writer,
+    } = params;
+    let CombatEvents {
+        mut hit_writer,
+        mut impact_writer,
+    } = events;
+    let PlayerWorld {
+        solids,
+        ladders,
+        waters,
+        mut slash_q,
+    } = world;
+    let dt = time.delta_secs();
+    if let Ok((mut transform, mut sprite, mut state, mut velocity, mut collider)) =
+        player_q.single_mut()
+    {
+        let mut pos = Vec2::new(transform.translation.x, transform.translation.y);
+        let set_respawn = actions.just_pressed(Action::SetRespawn);
+        let ladder = ladder_at(pos, collider.size, &ladders);
+        if ladder.is_none() {
+            state.on_ladder = false;
+        }
+
+        state.attack_buffer = (state.attack_buffer - dt).max(0.0);
+        state.combo_timer = (state.combo_timer - dt).max(0.0);
+        state.parry_timer = (state.parry_timer - dt).max(0.0);
+        state.parry_cooldown = (state.parry_cooldown - dt).max(0.0);
+        state.stagger_timer = (state.stagger_timer - dt).max(0.0);
+        if let Some(duration) = state.attack.as_ref().map(|attack| attack.duration) {
+            state.attack_timer += dt;
+            if state.attack_timer >= duration {
+                state.attack = None;
             }
-            try_hit_guard(pos, state.facing, &mut guards, &mut commands);
+        }
+
+        state.land_stun_timer = (state.land_stun_timer - dt).max(0.0);
+        state.hurt_timer = (state.hurt_timer - dt).max(0.0);
+        state.invincible_timer = (state.invincible_timer - dt).max(0.0);
+        let stunned = state.land_stun_timer > 0.0 || state.stagger_timer > 0.0;
+        let duel_x = guards
+            .iter()
+            .find(|(_, _, guard, _, _, _)| {
+                guard.alive
+                    && guard.downed_timer == 0.0
+                    && guard.ranged.is_none()
+                    && guard.state == GuardState::Attack
+            })
+            .map(|(_, guard_tf, _, _, _, _)| guard_tf.translation.x);
+        let jump_pressed = !stunned && actions.just_pressed(Action::Jump);
+
+        let on_ground = state.coyote_timer > 0.0;
+        let input_dir = if stunned { 0.0 } else { move_input(&actions) };
+        let climb_dir = if stunned { 0.0 } else { climb_input(&actions) };
+        if duel_x.is_some() && climb_dir > 0.0 {
+            if actions.just_pressed(Action::Up) && state.parry_cooldown == 0.0 {
+                state.parry_timer = 0.2;
+                state.parry_cooldown = 0.45;
+            }
+        } else if let (false, Some(ladder)) = (state.on_ladder, ladder) {
+            if ladder_grab(pos, collider.size, ladder, climb_dir, on_ground) {
+                state.on_ladder = true;
+                state.jump_held = false;
+                state.wall_jump_lock_timer = 0.0;
+                pos.x = ladder.0.x;
+                *velocity = Velocity(Vec2::ZERO);
+            }
+        }
+        let want_crouch = actions.pressed(Action::Down)
+            && on_ground
+            && !state.on_ladder;
+        let crouch_offset = (state.stand_height - state.crouch_height) * 0.5;
+        if want_crouch && !state.crouched {
+            state.crouched = true;
+            collider.size.y = state.crouch_height;
+            pos.y -= crouch_offset;
+        } else if !want_crouch && state.crouched {
+            let standing_pos = pos + Vec2::new(0.0, crouch_offset);
+            let standing_size = Vec2::new(collider.size.x, state.stand_height);
+            let blocked = solids.iter().any(|(solid_tf, solid_collider)| {
+                aabb_intersects(
+                    standing_pos,
+                    standing_size,
+                    Vec2::new(solid_tf.translation.x, solid_tf.translation.y),
+                    solid_collider.size,
+                )
+            });
+            if !blocked {
+                state.crouched = false;
+                collider.size.y = state.stand_height;
+                pos = standing_pos;
+            }
+        }
+        let jump_pressed = jump_pressed && !state.crouched;
+        let water = waters.iter().find_map(|(water_tf, water_collider, water)| {
+            let water_pos = Vec2::new(water_tf.translation.x, water_tf.translation.y);
+            let half = water_collider.size * 0.5;
+            ((pos.x - water_pos.x).abs() < half.x && (pos.y - water_pos.y).abs() < half.y)
+                .then_some((water_pos.y + half.y, water))
+        });
+        if let Some((surface, _)) = water {
+            if !state.in_water {
+                spawn_splash(&mut commands, Vec2::new(pos.x, surface));
+                play_sfx(&mut commands, audio.splash.clone(), db_to_linear(-4.0));
+            }
+            if pos.y + collider.size.y * 0.5 < surface {
+                state.air = (state.air - dt).max(0.0);
+                if state.air == 0.0 {
+                    state.air = state.air_time;
+                    hit_writer.write(PlayerHitEvent {
+                        damage: Damage::Kill,
+                        from: pos,
+                        cause: DeathCause::Drowning,
+                    });
+                }
+            } else {
+                state.air = (state.air + dt * 3.0).min(state.air_time);
+            }
+        } else {
+            state.air = (state.air + dt * 3.0).min(state.air_time);
+        }
+        state.in_water = water.is_some();
+        let mut max_speed = state.speed;
+        if state.crouched {
+            max_speed *= 0.4;
+        }
+        if state.pushing || state.pulling {
+            max_speed = max_speed.min(state.push_speed);
+        }
+        if duel_x.is_some() {
+            max_speed = max_speed.min(state.duel_speed);
+        }
+
+        if actions.just_pressed(Action::Attack) {
+            state.attack_buffer = attacks.buffer;
+        }
+        if !stunned && state.attack.is_none() && state.attack_buffer > 0.0 {
+            state.attack_buffer = 0.0;
+            let up = actions.pressed(Action::Up);
+            let down = actions.pressed(Action::Down);
+            let attack = if down && !on_ground && !state.on_ladder {
+                attacks.down.clone()
+            } else if up {
+                attacks.up.clone()
+            } else if state.crouched {
+                attacks.crouch.clone()
+            } else {
+                if state.combo_timer == 0.0 || state.combo_step >= attacks.combo.len() {
+                    state.combo_step = 0;
+                }
+                let attack = attacks.combo_move(state.combo_step);
+                state.combo_step += 1;
+                state.combo_timer = attack.duration + attacks.combo_window;
+                attack
+            };
+            state.attack = Some(attack);
+            state.attack_timer = 0.0;
+            state.attack_connected = false;
+        }
+        if !stunned
+            && !state.in_water
+            && !state.on_ladder
+            && actions.just_pressed(Action::Throw)
+            && state.daggers > 0
+        {
+            state.daggers -= 1;
+            spawn_projectile(
+                &mut commands,
+                assets.dagger.clone(),
+                pos + Vec2::new(8.0 * state.facing, 2.0),
+                Projectile {
+                    velocity: Vec2::new(DAGGER_SPEED * state.facing, 40.0),
+                    gravity_scale: 0.5,
+                    lifetime: 2.0,
+                    owner: ProjectileOwner::Player,
+                    damage: Damage::Hit(1),
+                    size: Vec2::new(6.0, 3.0),
+                },
+            );
+            play_sfx(&mut commands, audio.jump.clone(), db_to_linear(-12.0));
         }
 
         if state.on_ladder {
-            let climb_dir = climb_input(&keys);
-            velocity.x = input_dir * state.speed * 0.6;
+            velocity.x = 0.0;
             velocity.y = climb_dir * state.climb_speed;
-            if keys.just_pressed(KeyCode::Space) {
+            if jump_pressed {
                 state.on_ladder = false;
+                state.jump_held = true;
+                velocity.x = input_dir * state.speed;
                 velocity.y = state.jump_velocity;
                 play_sfx(&mut commands, audio.jump.clone(), db_to_linear(-6.0));
             }
+        } else if let Some((_, water)) = water {
+            velocity.x = input_dir * state.swim_speed;
+            state.coyote_timer = 0.0;
+            state.jump_buffer_timer = 0.0;
+            state.jump_held = false;
+            state.wall_jump_lock_timer = 0.0;
+            velocity.y += (state.gravity + water.buoyancy) * dt;
+            velocity.y -= velocity.y * (water.drag * dt).min(1.0);
+            if climb_dir < 0.0 {
+                velocity.y = -state.swim_speed;
+            }
+            if jump_pressed {
+                velocity.y = state.swim_stroke;
+            }
         } else {
-            velocity.x = input_dir * max_speed;
+            if state.wall_jump_lock_timer > 0.0 {
+                state.wall_jump_lock_timer = (state.wall_jump_lock_timer - dt).max(0.0);
+            } else if state.hurt_timer == 0.0 {
+                velocity.x = input_dir * max_speed;
+            }
             state.coyote_timer = (state.coyote_timer - dt).max(0.0);
-            if keys.just_pressed(KeyCode::Space) {
+            if jump_pressed {
                 state.jump_buffer_timer = state.jump_buffer;
             } else {
                 state.jump_buffer_timer = (state.jump_buffer_timer - dt).max(0.0);
@@ -1421,10 +4693,51 @@ fn player_system(
                 velocity.y = state.jump_velocity;
                 state.jump_buffer_timer = 0.0;
                 state.coyote_timer = 0.0;
+                state.jump_held = true;
                 play_sfx(&mut commands, audio.jump.clone(), db_to_linear(-6.0));
+                noise_writer.write(NoiseEvent {
+                    pos,
+                    radius: JUMP_NOISE_RADIUS,
+                    kind: NoiseKind::Jump,
+                });
+            } else if state.jump_buffer_timer > 0.0 && state.wall_dir != 0.0 {
+                velocity.x = -state.wall_dir * state.wall_jump_velocity.x;
+                velocity.y = state.wall_jump_velocity.y;
+                state.jump_buffer_timer = 0.0;
+                state.wall_jump_lock_timer = state.wall_jump_lock;
+                state.facing = -state.wall_dir;
+                state.wall_dir = 0.0;
+                state.jump_held = true;
+                play_sfx(&mut commands, audio.jump.clone(), db_to_linear(-6.0));
+                noise_writer.write(NoiseEvent {
+                    pos,
+                    radius: JUMP_NOISE_RADIUS,
+                    kind: NoiseKind::Jump,
+                });
+            }
+
+            if state.jump_held && !actions.pressed(Action::Jump) {
+                if velocity.y > 0.0 {
+                    velocity.y *= state.jump_cut;
+                }
+                state.jump_held = false;
             }
 
-            velocity.y += state.gravity * dt;
+            let gravity_scale = if !on_ground && velocity.y.abs() < state.apex_threshold {
+                state.apex_gravity_scale
+            } else if velocity.y < 0.0 {
+                state.fall_gravity_scale
+            } else {
+                1.0
+            };
+            velocity.y += state.gravity * gravity_scale * dt;
+            velocity.y = velocity.y.max(-state.max_fall_speed);
+            if velocity.y <= 0.0 {
+                state.jump_held = false;
+            }
+            if state.wall_dir != 0.0 && input_dir == state.wall_dir {
+                velocity.y = velocity.y.max(-state.wall_slide_speed);
+            }
         }
 
         let mut new_pos = pos;
@@ -1436,10 +4749,67 @@ fn player_system(
         if hit_y {
             velocity.y = 0.0;
         }
-        let on_floor = hit_y && delta.y < 0.0;
+        let mut on_floor = hit_y && delta.y < 0.0;
+        if state.on_ladder {
+            if let Some(ladder) = ladder {
+                if ladder_climbed_out(&mut new_pos, collider.size, state.facing, ladder, &solids) {
+                    state.on_ladder = false;
+                    velocity.y = 0.0;
+                    on_floor = true;
+                } else if on_floor {
+                    state.on_ladder = false;
+                }
+            }
+        } else if delta.y < 0.0 && land_on_ladder_top(&mut new_pos, pos.y, collider.size, &ladders) {
+            velocity.y = 0.0;
+            on_floor = true;
+        }
         if on_floor {
             state.coyote_timer = state.coyote_time;
         }
+        let pushing_wall = hit_x && input_dir != 0.0 && delta.x.signum() == input_dir;
+        let can_wall_slide = state.wall_jump_enabled && !state.on_ladder && !state.in_water;
+        state.wall_dir = if can_wall_slide && pushing_wall && !on_floor {
+            input_dir
+        } else {
+            0.0
+        };
+
+        if on_floor && !state.grounded {
+            let fall_distance = state.fall_apex_y - new_pos.y;
+            let below = new_pos - Vec2::Y;
+            if fall_distance > 8.0 {
+                noise_writer.write(NoiseEvent {
+                    pos: new_pos,
+                    radius: LAND_NOISE_RADIUS + fall_distance * 0.8,
+                    kind: NoiseKind::Landing,
+                });
+            }
+            if fall_distance >= fall.fatal_distance {
+                hit_writer.write(PlayerHitEvent {
+                    damage: Damage::Kill,
+                    from: below,
+                    cause: DeathCause::Fall,
+                });
+            } else if fall_distance >= fall.hurt_distance {
+                state.land_stun_timer = fall.hurt_time;
+                hit_writer.write(PlayerHitEvent {
+                    damage: Damage::Hit(1),
+                    from: below,
+                    cause: DeathCause::Fall,
+                });
+                play_sfx(&mut commands, audio.land.clone(), 1.0);
+            } else if fall_distance >= fall.stun_distance {
+                state.land_stun_timer = fall.stun_time;
+                play_sfx(&mut commands, audio.land.clone(), db_to_linear(-6.0));
+            }
+        }
+        if on_floor || state.on_ladder || state.in_water || state.wall_dir != 0.0 {
+            state.fall_apex_y = new_pos.y;
+        } else {
+            state.fall_apex_y = state.fall_apex_y.max(new_pos.y);
+        }
+        state.grounded = on_floor;
 
         transform.translation.x = new_pos.x;
         transform.translation.y = new_pos.y;
@@ -1447,76 +4817,1279 @@ fn player_system(
             state.respawn_position = new_pos;
         }
 
-        if input_dir.abs() > 0.1 {
+        let locked = state.wall_jump_lock_timer > 0.0 || state.hurt_timer > 0.0;
+        if let Some(guard_x) = duel_x {
+            state.facing = if guard_x < new_pos.x { -1.0 } else { 1.0 };
+        } else if !locked && !state.pulling && input_dir.abs() > 0.1 {
             state.facing = input_dir.signum();
-            sprite.flip_x = state.facing < 0.0;
         }
+        sprite.flip_x = state.facing < 0.0;
+        let flicker = state.invincible_timer > 0.0 && (state.invincible_timer * 8.0).fract() < 0.5;
+        sprite.color = if state.parry_timer > 0.0 {
+            Color::srgb(0.75, 0.9, 1.0)
+        } else if state.stagger_timer > 0.0 {
+            Color::srgb(0.65, 0.65, 0.7)
+        } else {
+            Color::WHITE
+        };
+        sprite.color.set_alpha(if flicker { 0.3 } else { 1.0 });
 
-        let moving = velocity.x.abs() > 1.0 && on_floor && !state.on_ladder;
-        if moving {
+        let moving = (velocity.x.abs() > 1.0 || state.pushing) && on_floor && !state.on_ladder;
+        if state.land_stun_timer > 0.0 {
+            if let Some(atlas) = &mut sprite.texture_atlas {
+                atlas.index = 2;
+            }
+        } else if state.crouched {
+            if moving {
+                state.walk_timer += dt * 5.0;
+            }
+            if let Some(atlas) = &mut sprite.texture_atlas {
+                atlas.index = if moving { 4 + state.walk_timer as usize % 2 } else { 3 };
+            }
+        } else if moving {
             state.walk_timer += dt * 8.0;
             if let Some(atlas) = &mut sprite.texture_atlas {
                 atlas.index = state.walk_timer as usize % 2;
             }
-        } else if let Some(atlas) = &mut sprite.texture_atlas {
-            atlas.index = 0;
-        }
+        } else if let Some(atlas) = &mut sprite.texture_atlas {
+            atlas.index = 0;
+        }
+
+        let dir = if sprite.flip_x { -1.0 } else { 1.0 };
+        let (windup, hitbox, damage, bounce, slash) = match &state.attack {
+            Some(attack) => (
+                attack.windup(state.attack_timer),
+                attack.hitbox(state.attack_timer).copied(),
+                attack.damage,
+                attack.bounce,
+                attack.slash,
+            ),
+            None => (0.0, None, 0, 0.0, (14.0, 4.0, 0.0)),
+        };
+        state.attack_windup = windup;
+        state.attack_hitbox = hitbox.map(|hitbox| {
+            (
+                new_pos + Vec2::new(hitbox.offset.0 * dir, hitbox.offset.1),
+                Vec2::new(hitbox.size.0, hitbox.size.1),
+            )
+        });
+        if let Ok((mut slash_tf, mut slash_sprite, mut visibility)) =
+            slash_q.get_mut(state.slash_entity)
+        {
+            slash_tf.translation.x = slash.0 * dir;
+            slash_tf.translation.y = slash.1;
+            slash_tf.rotation = Quat::from_rotation_z(slash.2.to_radians() * dir);
+            slash_sprite.flip_x = sprite.flip_x;
+            *visibility = if state.attack_hitbox.is_some() {
+                Visibility::Visible
+            } else {
+                Visibility::Hidden
+            };
+        }
+
+        if let (Some(sword), false) = (state.attack_hitbox, state.attack_connected) {
+            match try_hit_guard(sword, dir, damage, &mut guards, &mut commands, &mut impact_writer) {
+                SwordHit::Miss => {}
+                SwordHit::Hit => {
+                    state.attack_connected = true;
+                    if bounce > 0.0 {
+                        velocity.y = bounce;
+                        state.jump_held = false;
+                    }
+                    play_sfx(&mut commands, audio.hit.clone(), db_to_linear(-3.0));
+                }
+                SwordHit::Parried => {
+                    state.attack_connected = true;
+                    state.attack = None;
+                    state.attack_hitbox = None;
+                    state.combo_timer = 0.0;
+                    state.stagger_timer = 0.45;
+                    if let Ok((_, _, mut visibility)) = slash_q.get_mut(state.slash_entity) {
+                        *visibility = Visibility::Hidden;
+                    }
+                    impact_writer.write(ImpactEvent {
+                        kind: ImpactKind::Parry,
+                        pos: sword.0,
+                        dir: -dir,
+                        target: None,
+                    });
+                    play_sfx(&mut commands, audio.clang.clone(), db_to_linear(-3.0));
+                }
+            }
+        }
+    }
+}
+
+fn spawn_splash(commands: &mut Commands, pos: Vec2) {
+    for index in 0..8 {
+        let spread = index as f32 / 7.0 - 0.5;
+        commands.spawn((
+            Sprite {
+                color: Color::srgba(0.7, 0.85, 1.0, 0.9),
+                custom_size: Some(Vec2::splat(2.0)),
+                ..default()
+            },
+            Transform::from_xyz(pos.x + spread * 10.0, pos.y, Z_WATER + 0.5),
+            Particle {
+                velocity: Vec2::new(spread * 90.0, 70.0 + (index % 3) as f32 * 20.0),
+                gravity: -400.0,
+            },
+            FadeOut {
+                timer: Timer::from_seconds(0.5, TimerMode::Once),
+            },
+            DespawnOnExit(AppState::InGame),
+        ));
+    }
+}
+
+fn air_hud_system(
+    session: Res<SessionState>,
+    player_q: Query<&PlayerState, With<Player>>,
+    mut bars: Query<&mut Visibility>,
+    mut fills: Query<&mut Node>,
+) {
+    let Ok(state) = player_q.single() else {
+        return;
+    };
+    let show = state.air < state.air_time;
+    if let Some(mut visibility) = session.hud_air_bar.and_then(|bar| bars.get_mut(bar).ok()) {
+        *visibility = if show { Visibility::Inherited } else { Visibility::Hidden };
+    }
+    if let Some(mut node) = session.hud_air_fill.and_then(|fill| fills.get_mut(fill).ok()) {
+        node.width = percent(100.0 * state.air / state.air_time.max(0.01));
+    }
+}
+
+fn guard_system(
+    ctx: ActorContext,
+    events: CombatEvents,
+    mut noise_reader: MessageReader<NoiseEvent>,
+    mut commands: Commands,
+    world: GuardWorld,
+    mut guards: GuardBodies,
+) {
+    let ActorContext {
+        time,
+        assets,
+        audio,
+    } = ctx;
+    let CombatEvents {
+        mut hit_writer,
+        mut impact_writer,
+    } = events;
+    let GuardWorld {
+        player_q,
+        lights,
+        solids,
+        ladders,
+    } = world;
+    let dt = time.delta_secs();
+    let player = player_q.single().ok().map(|(player_tf, state)| {
+        (
+            Vec2::new(player_tf.translation.x, player_tf.translation.y),
+            state,
+        )
+    });
+    let visibility = player.map_or(0.0, |(player_pos, state)| {
+        player_visibility(player_pos, state.crouched, &lights)
+    });
+    let noises: Vec<(Vec2, f32)> = noise_reader
+        .read()
+        .map(|noise| (noise.pos, noise.radius))
+        .collect();
+    for (entity, mut transform, mut sprite, mut guard, mut velocity, collider, mut health) in
+        guards.iter_mut()
+    {
+        if !guard.alive {
+            continue;
+        }
+
+        let start = Vec2::new(transform.translation.x, transform.translation.y);
+        let ladder = ladder_at(start, collider.size, &ladders);
+        if ladder.is_none() {
+            guard.climb_dir = 0.0;
+        }
+        guard.strike_cooldown = (guard.strike_cooldown - dt).max(0.0);
+        guard.fire_cooldown = (guard.fire_cooldown - dt).max(0.0);
+        guard.parry_timer = (guard.parry_timer - dt).max(0.0);
+        guard.stagger_timer = (guard.stagger_timer - dt).max(0.0);
+
+        let leash_left = guard.left_limit - guard.leash;
+        let leash_right = guard.right_limit + guard.leash;
+        let ladder_to = |goal_y: f32| {
+            ladder_toward(start, collider.size, goal_y - start.y, &ladders)
+                .filter(|x| *x >= leash_left && *x <= leash_right)
+                .map(|x| (x, (goal_y - start.y).signum()))
+        };
+        let sight = player.map_or(0.0, |(player_pos, _)| {
+            guard_sight(start, guard.direction, player_pos, &solids)
+        });
+        let sees_player = sight * visibility > 0.1;
+
+        if guard.downed_timer > 0.0 {
+            guard.downed_timer = (guard.downed_timer - dt).max(0.0);
+            if guard.downed_timer == 0.0 {
+                health.current = health.max;
+                guard.state = GuardState::Alert;
+                guard.state_timer = GUARD_ALERT_TIME;
+                guard.awareness = 1.0;
+            }
+        } else {
+            if guard.state.is_aware() {
+                if sees_player {
+                    guard.awareness = 1.0;
+                }
+            } else if sight > 0.0 {
+                guard.awareness += dt * GUARD_NOTICE_RATE * sight * visibility;
+            } else {
+                guard.awareness = (guard.awareness - dt * GUARD_AWARENESS_DECAY).max(0.0);
+            }
+            if !guard.state.is_aware() {
+                for (noise_pos, radius) in noises.iter().copied() {
+                    let offset = noise_pos - start;
+                    if offset.length() < radius && offset.y.abs() < GUARD_HEARING_HEIGHT {
+                        guard.awareness += 0.2 + 0.6 * (1.0 - offset.length() / radius);
+                        guard.direction = if offset.x < 0.0 { -1.0 } else { 1.0 };
+                    }
+                }
+            }
+            guard.awareness = guard.awareness.min(1.0);
+            let previous = guard.state;
+            guard.state_timer = (guard.state_timer - dt).max(0.0);
+
+            match (guard.state, player) {
+                (GuardState::Patrol | GuardState::Return, Some((player_pos, _)))
+                    if guard.awareness >= 1.0 =>
+                {
+                    guard.state = GuardState::Alert;
+                    guard.state_timer = GUARD_ALERT_TIME;
+                    guard.direction = (player_pos.x - start.x).signum();
+                    play_sfx(&mut commands, audio.alert.clone(), db_to_linear(-3.0));
+                }
+                (GuardState::Alert, _) if guard.state_timer == 0.0 => {
+                    guard.state = GuardState::Chase;
+                }
+                (GuardState::Chase | GuardState::Attack, Some((player_pos, state))) => {
+                    let offset = player_pos - start;
+                    let toward = offset.x.signum();
+                    let in_leash = player_pos.x > leash_left - DUEL_RANGE
+                        && player_pos.x < leash_right + DUEL_RANGE;
+                    if sees_player && in_leash {
+                        guard.lost_timer = 0.0;
+                    } else {
+                        guard.lost_timer += dt;
+                    }
+                    let (engage, disengage) = match guard.ranged {
+                        Some(ranged) => (
+                            sees_player && offset.x.abs() < ranged.preferred_range + 24.0,
+                            offset.x.abs() > ranged.preferred_range + 60.0,
+                        ),
+                        None => {
+                            let same_floor = offset.y.abs() < DUEL_FLOOR_TOLERANCE;
+                            (
+                                same_floor
+                                    && offset.x.abs() < DUEL_RANGE
+                                    && state.facing == -toward
+                                    && guard.direction == toward,
+                                !same_floor || offset.x.abs() > DUEL_BREAK_RANGE,
+                            )
+                        }
+                    };
+                    if guard.lost_timer >= GUARD_GIVE_UP_TIME {
+                        guard.state = GuardState::Return;
+                    } else if guard.state == GuardState::Chase && engage {
+                        guard.state = GuardState::Attack;
+                    } else if guard.state == GuardState::Attack && disengage {
+                        guard.state = GuardState::Chase;
+                    }
+                }
+                (GuardState::Alert | GuardState::Chase | GuardState::Attack, None) => {
+                    guard.state = GuardState::Return;
+                }
+                (GuardState::Return, _)
+                    if start.x >= guard.left_limit
+                        && start.x <= guard.right_limit
+                        && ladder_to(guard.home_y).is_none() =>
+                {
+                    guard.state = GuardState::Patrol;
+                }
+                _ => {}
+            }
+            if guard.state != previous {
+                guard.lost_timer = 0.0;
+                guard.windup_timer = 0.0;
+                if guard.state == GuardState::Return {
+                    guard.awareness = 0.5;
+                }
+            }
+        }
+
+        let knocked = guard.downed_timer > 0.0 || guard.stagger_timer > 0.0;
+        let climb_to = match (guard.state, player) {
+            (GuardState::Chase, Some((player_pos, _))) => ladder_to(player_pos.y),
+            (GuardState::Return, _) => ladder_to(guard.home_y),
+            _ => None,
+        };
+        let mut grab_x = None;
+        if knocked {
+            guard.windup_timer = 0.0;
+            guard.climb_dir = 0.0;
+            velocity.x *= (1.0 - 8.0 * dt).max(0.0);
+        } else if guard.climb_dir != 0.0 {
+            velocity.x = 0.0;
+            velocity.y = guard.climb_dir * guard.climb_speed;
+        } else if let (GuardState::Attack, Some((player_pos, state))) = (guard.state, player) {
+            let offset = player_pos - start;
+            let toward = offset.x.signum();
+            guard.direction = toward;
+            if state.attack_windup <= 0.0 {
+                guard.reacted = false;
+            }
+
+            if let Some(ranged) = guard.ranged {
+                let distance = offset.x.abs();
+                if guard.windup_timer > 0.0 {
+                    velocity.x = 0.0;
+                    guard.windup_timer = (guard.windup_timer - dt).max(0.0);
+                    if guard.windup_timer == 0.0 {
+                        guard.fire_cooldown = ranged.cooldown;
+                        let origin = start + Vec2::new(8.0 * toward, 2.0);
+                        let aim = (player_pos - origin).normalize_or(Vec2::X * toward);
+                        spawn_projectile(
+                            &mut commands,
+                            assets.arrow.clone(),
+                            origin,
+                            Projectile {
+                                velocity: aim * ranged.projectile_speed,
+                                gravity_scale: 0.1,
+                                lifetime: ARROW_LIFETIME,
+                                owner: ProjectileOwner::Enemy,
+                                damage: Damage::Hit(1),
+                                size: Vec2::new(6.0, 2.0),
+                            },
+                        );
+                    }
+                } else if distance < ranged.preferred_range - 24.0 {
+                    velocity.x = -toward * guard.duel_speed;
+                } else if distance > ranged.preferred_range + 24.0 {
+                    velocity.x = toward * guard.duel_speed;
+                } else {
+                    velocity.x = 0.0;
+                    if guard.fire_cooldown == 0.0 && sees_player {
+                        guard.windup_timer = ranged.windup;
+                    }
+                }
+            } else if guard.windup_timer > 0.0 {
+                velocity.x = 0.0;
+                guard.windup_timer = (guard.windup_timer - dt).max(0.0);
+                if guard.windup_timer == 0.0 {
+                    guard.strike_cooldown = 0.8;
+                    let in_reach = offset.x.abs() < GUARD_STRIKE_REACH
+                        && offset.y.abs() < DUEL_FLOOR_TOLERANCE;
+                    if in_reach && state.parry_timer > 0.0 && state.facing == -toward {
+                        guard.stagger_timer = 0.6;
+                        impact_writer.write(ImpactEvent {
+                            kind: ImpactKind::Parry,
+                            pos: start + offset * 0.5,
+                            dir: -toward,
+                            target: None,
+                        });
+                        play_sfx(&mut commands, audio.clang.clone(), db_to_linear(-3.0));
+                    } else if in_reach {
+                        hit_writer.write(PlayerHitEvent {
+                            damage: Damage::Hit(1),
+                            from: start,
+                            cause: DeathCause::Guard,
+                        });
+                    }
+                }
+            } else {
+                if state.attack_windup > 0.0 && !guard.reacted {
+                    guard.reacted = true;
+                    if next_random(&mut guard.rng) < guard.parry_chance {
+                        guard.parry_timer = 0.3;
+                    }
+                }
+
+                let distance = offset.x.abs();
+                if guard.parry_timer > 0.0 {
+                    velocity.x = 0.0;
+                } else if distance > GUARD_STRIKE_REACH - 4.0 {
+                    velocity.x = toward * guard.duel_speed;
+                } else if distance < 16.0 {
+                    velocity.x = -toward * guard.duel_speed;
+                } else {
+                    velocity.x = 0.0;
+                    if guard.strike_cooldown == 0.0 {
+                        guard.windup_timer = guard.windup;
+                    }
+                }
+            }
+        } else if let Some((ladder_x, climb)) = climb_to {
+            let scale = if guard.state == GuardState::Chase { GUARD_CHASE_SCALE } else { 1.0 };
+            if (ladder_x - start.x).abs() > 2.0 {
+                guard.direction = (ladder_x - start.x).signum();
+                velocity.x = guard.speed * guard.speed_scale * scale * guard.direction;
+            } else {
+                velocity.x = 0.0;
+                if let Some(ladder) = ladder {
+                    if ladder_grab(start, collider.size, ladder, climb, true) {
+                        guard.climb_dir = climb;
+                        grab_x = Some(ladder.0.x);
+                        **velocity = Vec2::ZERO;
+                    }
+                }
+            }
+        } else {
+            let speed = guard.speed * guard.speed_scale;
+            match (guard.state, player) {
+                (GuardState::Alert, _) => {
+                    velocity.x = 0.0;
+                }
+                (GuardState::Chase, Some((player_pos, _))) => {
+                    let target = player_pos.x.clamp(leash_left, leash_right);
+                    if (target - start.x).abs() > 2.0 {
+                        guard.direction = (target - start.x).signum();
+                        velocity.x = speed * GUARD_CHASE_SCALE * guard.direction;
+                    } else {
+                        guard.direction = (player_pos.x - start.x).signum();
+                        velocity.x = 0.0;
+                    }
+                }
+                (GuardState::Return, _) => {
+                    let target = start.x.clamp(guard.left_limit, guard.right_limit);
+                    guard.direction = if target < start.x { -1.0 } else { 1.0 };
+                    velocity.x = speed * guard.direction;
+                }
+                _ => {
+                    velocity.x = speed * guard.direction;
+                }
+            }
+        }
+        if guard.climb_dir == 0.0 {
+            velocity.y += guard.gravity * dt;
+        }
+
+        let mut pos = start;
+        if let Some(x) = grab_x {
+            pos.x = x;
+        }
+        let delta = **velocity * dt;
+        let (hit_x, hit_y) = move_with_collisions(&mut pos, delta, collider.size, &solids);
+        if hit_x {
+            velocity.x = 0.0;
+        }
+        if hit_y {
+            velocity.y = 0.0;
+        }
+        if guard.climb_dir != 0.0 {
+            if let Some(ladder) = ladder {
+                if ladder_climbed_out(&mut pos, collider.size, guard.direction, ladder, &solids) {
+                    guard.climb_dir = 0.0;
+                    velocity.y = 0.0;
+                } else if hit_y && delta.y < 0.0 {
+                    guard.climb_dir = 0.0;
+                }
+            }
+        } else if delta.y < 0.0 && land_on_ladder_top(&mut pos, start.y, collider.size, &ladders) {
+            velocity.y = 0.0;
+        }
+
+        if !knocked {
+            if guard.state == GuardState::Patrol {
+                if pos.x <= guard.left_limit {
+                    pos.x = guard.left_limit;
+                    guard.direction = 1.0;
+                } else if pos.x >= guard.right_limit {
+                    pos.x = guard.right_limit;
+                    guard.direction = -1.0;
+                }
+            } else {
+                pos.x = pos.x.clamp(leash_left.min(start.x), leash_right.max(start.x));
+            }
+        }
+
+        transform.translation.x = pos.x;
+        transform.translation.y = pos.y;
+        if pos.y < 0.0 {
+            kill_guard(&mut commands, entity, &mut guard, &mut velocity);
+            continue;
+        }
+
+        sprite.flip_x = guard.direction < 0.0;
+        sprite.color = if guard.windup_timer > 0.0 {
+            Color::srgb(1.0, 0.6, 0.5)
+        } else if guard.parry_timer > 0.0 {
+            Color::srgb(0.75, 0.9, 1.0)
+        } else if guard.stagger_timer > 0.0 {
+            Color::srgb(0.65, 0.65, 0.7)
+        } else {
+            Color::WHITE
+        };
+        if velocity.x.abs() > 1.0 {
+            guard.walk_timer += dt * 6.0;
+        }
+        let frame = match (guard.down_frame, guard.aim_frame) {
+            (Some(frame), _) if guard.downed_timer > 0.0 => frame,
+            (_, Some(frame)) if guard.windup_timer > 0.0 => frame,
+            _ => guard.walk_timer as usize % 2,
+        };
+        if let Some(atlas) = &mut sprite.texture_atlas {
+            atlas.index = frame;
+        }
+    }
+}
+
+fn spawn_projectile(
+    commands: &mut Commands,
+    image: Handle<Image>,
+    origin: Vec2,
+    projectile: Projectile,
+) {
+    commands.spawn((
+        Sprite::from_image(image),
+        Transform {
+            translation: Vec3::new(origin.x, origin.y, Z_ACTOR + 0.5),
+            rotation: Quat::from_rotation_z(projectile.velocity.to_angle()),
+            ..default()
+        },
+        projectile,
+        DespawnOnExit(AppState::InGame),
+    ));
+}
+
+fn projectile_system(
+    ctx: ActorContext,
+    mut commands: Commands,
+    events: CombatEvents,
+    solids: Colliders<(With<Solid>, Without<Projectile>)>,
+    targets: ProjectileTargets,
+    mut projectiles: Query<(Entity, &mut Transform, &mut Projectile)>,
+) {
+    let ActorContext { time, audio, .. } = ctx;
+    let CombatEvents {
+        mut hit_writer,
+        mut impact_writer,
+    } = events;
+    let ProjectileTargets {
+        player_q,
+        mut guards,
+        mut bosses,
+    } = targets;
+    let dt = time.delta_secs();
+    let player = player_q.single().ok();
+    for (entity, mut transform, mut projectile) in projectiles.iter_mut() {
+        projectile.lifetime -= dt;
+        projectile.velocity.y += PROJECTILE_GRAVITY * projectile.gravity_scale * dt;
+        let mut pos = Vec2::new(transform.translation.x, transform.translation.y);
+        let (hit_x, hit_y) =
+            move_with_collisions(&mut pos, projectile.velocity * dt, projectile.size, &solids);
+        transform.translation.x = pos.x;
+        transform.translation.y = pos.y;
+        transform.rotation = Quat::from_rotation_z(projectile.velocity.to_angle());
+
+        let mut spent = hit_x || hit_y || projectile.lifetime <= 0.0;
+        if let (false, ProjectileOwner::Enemy | ProjectileOwner::Trap, Some((player_tf, collider))) =
+            (spent, projectile.owner, player)
+        {
+            let player_pos = Vec2::new(player_tf.translation.x, player_tf.translation.y);
+            if aabb_intersects(pos, projectile.size, player_pos, collider.size) {
+                hit_writer.write(PlayerHitEvent {
+                    damage: projectile.damage,
+                    from: pos,
+                    cause: if projectile.owner == ProjectileOwner::Trap {
+                        DeathCause::Trap
+                    } else {
+                        DeathCause::Projectile
+                    },
+                });
+                spent = true;
+            }
+        }
+        if projectile.owner != ProjectileOwner::Enemy && !spent {
+            for (guard_entity, guard_tf, mut guard, collider, mut velocity, mut health) in
+                guards.iter_mut()
+            {
+                if !guard.alive || guard.downed_timer > 0.0 {
+                    continue;
+                }
+                let guard_pos = Vec2::new(guard_tf.translation.x, guard_tf.translation.y);
+                if aabb_intersects(pos, projectile.size, guard_pos, collider.size) {
+                    let amount = match projectile.damage {
+                        Damage::Hit(amount) => amount,
+                        Damage::Kill => health.current,
+                    };
+                    let kind = damage_guard(
+                        &mut commands,
+                        guard_entity,
+                        &mut guard,
+                        &mut velocity,
+                        &mut health,
+                        amount,
+                        projectile.velocity.x.signum(),
+                    );
+                    impact_writer.write(ImpactEvent {
+                        kind,
+                        pos,
+                        dir: projectile.velocity.x.signum(),
+                        target: Some(guard_entity),
+                    });
+                    play_sfx(&mut commands, audio.hit.clone(), db_to_linear(-3.0));
+                    spent = true;
+                    break;
+                }
+            }
+        }
+        if projectile.owner == ProjectileOwner::Player && !spent {
+            for (boss_entity, boss_tf, collider, mut boss, mut health) in bosses.iter_mut() {
+                let boss_pos = Vec2::new(boss_tf.translation.x, boss_tf.translation.y);
+                if boss.active
+                    && !boss.defeated
+                    && aabb_intersects(pos, projectile.size, boss_pos, collider.size)
+                {
+                    damage_boss(&mut boss, &mut health, 1);
+                    impact_writer.write(ImpactEvent {
+                        kind: ImpactKind::Hit,
+                        pos,
+                        dir: projectile.velocity.x.signum(),
+                        target: Some(boss_entity),
+                    });
+                    play_sfx(&mut commands, audio.hit.clone(), db_to_linear(-3.0));
+                    spent = true;
+                    break;
+                }
+            }
+        }
+        if spent {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn boss_system(
+    ctx: ActorContext,
+    mut commands: Commands,
+    events: CombatEvents,
+    mut player_q: PlayerTarget<Without<Boss>>,
+    solids: Colliders<(With<Solid>, Without<Boss>, Without<Player>)>,
+    mut gates: Query<(&ArenaGate, &mut Gate)>,
+    mut bosses: BossBodies,
+) {
+    let ActorContext {
+        time,
+        assets,
+        audio,
+    } = ctx;
+    let CombatEvents {
+        mut hit_writer,
+        mut impact_writer,
+    } = events;
+    let dt = time.delta_secs();
+    let Ok((player_tf, player_collider, mut state, mut player_velocity)) = player_q.single_mut()
+    else {
+        return;
+    };
+    let player_pos = Vec2::new(player_tf.translation.x, player_tf.translation.y);
+
+    for (entity, mut transform, mut sprite, mut boss, mut velocity, collider, mut health) in
+        bosses.iter_mut()
+    {
+        if boss.defeated {
+            continue;
+        }
+        let start = Vec2::new(transform.translation.x, transform.translation.y);
+        let offset = player_pos - start;
+        if !boss.active {
+            if player_pos.x > boss.arena_left + 8.0 && offset.y.abs() < 40.0 {
+                boss.active = true;
+                for (arena_gate, mut gate) in gates.iter_mut() {
+                    if arena_gate.boss == entity {
+                        gate.open = false;
+                    }
+                }
+                play_sfx(&mut commands, audio.alert.clone(), 1.0);
+            }
+            continue;
+        }
+
+        boss.hit_timer = (boss.hit_timer - dt).max(0.0);
+        if let (Some((sword_pos, sword_size)), false) = (state.attack_hitbox, state.attack_connected)
+        {
+            if aabb_intersects(sword_pos, sword_size, start, collider.size) {
+                state.attack_connected = true;
+                let (damage, bounce) = state
+                    .attack
+                    .as_ref()
+                    .map_or((1, 0.0), |attack| (attack.damage, attack.bounce));
+                if bounce > 0.0 {
+                    player_velocity.y = bounce;
+                    state.jump_held = false;
+                }
+                damage_boss(&mut boss, &mut health, damage);
+                impact_writer.write(ImpactEvent {
+                    kind: if health.current == 0 {
+                        ImpactKind::Kill
+                    } else {
+                        ImpactKind::Hit
+                    },
+                    pos: start,
+                    dir: state.facing,
+                    target: Some(entity),
+                });
+                play_sfx(&mut commands, audio.hit.clone(), db_to_linear(-3.0));
+            }
+        }
+
+        if health.current == 0 {
+            boss.defeated = true;
+            **velocity = Vec2::ZERO;
+            for (arena_gate, mut gate) in gates.iter_mut() {
+                if arena_gate.boss == entity {
+                    gate.open = true;
+                }
+            }
+            commands.entity(entity).remove::<Collider>();
+            commands.entity(entity).insert(FadeOut {
+                timer: Timer::from_seconds(1.2, TimerMode::Once),
+            });
+            play_sfx(&mut commands, audio.door.clone(), 1.0);
+            continue;
+        }
+
+        let fraction = health.current as f32 / health.max as f32;
+        let phase = boss
+            .phases
+            .iter()
+            .position(|phase| fraction > phase.until)
+            .unwrap_or(boss.phases.len().saturating_sub(1));
+        if phase != boss.phase {
+            boss.phase = phase;
+            boss.step = 0;
+            boss.timer = 0.0;
+            boss.done = false;
+            boss.stagger_timer = BOSS_PHASE_STAGGER;
+            play_sfx(&mut commands, audio.clang.clone(), 1.0);
+        }
+
+        let toward = if offset.x < 0.0 { -1.0 } else { 1.0 };
+        let mut frame = 0;
+        let mut windup = false;
+        if boss.stagger_timer > 0.0 {
+            boss.stagger_timer = (boss.stagger_timer - dt).max(0.0);
+            velocity.x = 0.0;
+        } else if let Some(phase) = boss.phases.get(boss.phase).cloned() {
+            let Some(action) = phase.pattern.get(boss.step).copied() else {
+                boss.step = 0;
+                continue;
+            };
+            boss.timer += dt;
+            let timer = boss.timer;
+            match action {
+                BossAction::Wait { duration } => {
+                    velocity.x = 0.0;
+                    boss.direction = toward;
+                    boss.done = timer >= duration;
+                }
+                BossAction::Walk { duration } => {
+                    boss.direction = toward;
+                    velocity.x = toward * phase.speed;
+                    boss.done = timer >= duration || offset.x.abs() < 20.0;
+                }
+                BossAction::Strike {
+                    windup: strike_windup,
+                    reach,
+                    damage,
+                } => {
+                    velocity.x = 0.0;
+                    windup = true;
+                    frame = 1;
+                    if timer >= strike_windup {
+                        let facing = boss.direction;
+                        let in_reach = offset.x * facing > 0.0
+                            && offset.x.abs() < reach
+                            && offset.y.abs() < collider.size.y * 0.5;
+                        if in_reach && state.parry_timer > 0.0 && state.facing == -facing {
+                            boss.stagger_timer = 0.6;
+                            impact_writer.write(ImpactEvent {
+                                kind: ImpactKind::Parry,
+                                pos: start + offset * 0.5,
+                                dir: -facing,
+                                target: None,
+                            });
+                            play_sfx(&mut commands, audio.clang.clone(), db_to_linear(-3.0));
+                        } else if in_reach {
+                            hit_writer.write(PlayerHitEvent {
+                                damage: Damage::Hit(damage),
+                                from: start,
+                                cause: DeathCause::Boss,
+                            });
+                        }
+                        boss.done = true;
+                    } else {
+                        boss.direction = toward;
+                    }
+                }
+                BossAction::Volley {
+                    windup: volley_windup,
+                    count,
+                    spread,
+                    speed,
+                } => {
+                    velocity.x = 0.0;
+                    windup = true;
+                    frame = 2;
+                    boss.direction = toward;
+                    if timer >= volley_windup {
+                        let origin = start + Vec2::new(14.0 * toward, 6.0);
+                        let aim = (player_pos - origin).normalize_or(Vec2::X * toward);
+                        for index in 0..count {
+                            let angle = spread * (index as f32 - (count as f32 - 1.0) * 0.5);
+                            spawn_projectile(
+                                &mut commands,
+                                assets.orb.clone(),
+                                origin,
+                                Projectile {
+                                    velocity: Vec2::from_angle(angle).rotate(aim) * speed,
+                                    gravity_scale: 0.0,
+                                    lifetime: 3.0,
+                                    owner: ProjectileOwner::Enemy,
+                                    damage: Damage::Hit(1),
+                                    size: Vec2::splat(4.0),
+                                },
+                            );
+                        }
+                        boss.done = true;
+                    }
+                }
+                BossAction::Dash {
+                    windup: dash_windup,
+                    speed,
+                    duration,
+                } => {
+                    if timer < dash_windup {
+                        velocity.x = 0.0;
+                        windup = true;
+                        frame = 1;
+                        boss.direction = toward;
+                    } else {
+                        velocity.x = boss.direction * speed;
+                    }
+                    boss.done = timer >= dash_windup + duration;
+                }
+            }
+            if boss.done {
+                boss.done = false;
+                boss.timer = 0.0;
+                boss.step = (boss.step + 1) % phase.pattern.len();
+            }
+        }
+
+        velocity.y += PROJECTILE_GRAVITY * dt;
+        let mut pos = start;
+        let delta = **velocity * dt;
+        let (hit_x, hit_y) = move_with_collisions(&mut pos, delta, collider.size, &solids);
+        if hit_x {
+            velocity.x = 0.0;
+        }
+        if hit_y {
+            velocity.y = 0.0;
+        }
+        let half = collider.size.x * 0.5;
+        pos.x = pos.x.clamp(boss.arena_left + half, boss.arena_right - half);
+        transform.translation.x = pos.x;
+        transform.translation.y = pos.y;
+
+        if aabb_intersects(player_pos, player_collider.size, pos, collider.size) {
+            hit_writer.write(PlayerHitEvent {
+                damage: Damage::Hit(1),
+                from: pos,
+                cause: DeathCause::Boss,
+            });
+        }
+
+        sprite.flip_x = boss.direction < 0.0;
+        sprite.color = if boss.hit_timer > 0.0 {
+            Color::srgb(1.0, 0.55, 0.55)
+        } else if boss.stagger_timer > 0.0 {
+            Color::srgb(0.65, 0.65, 0.75)
+        } else if windup {
+            Color::srgb(1.0, 0.8, 0.6)
+        } else {
+            Color::WHITE
+        };
+        if let Some(atlas) = &mut sprite.texture_atlas {
+            atlas.index = frame;
+        }
+    }
+}
+
+fn damage_boss(boss: &mut Boss, health: &mut Health, amount: u32) {
+    health.current = health.current.saturating_sub(amount);
+    boss.hit_timer = 0.15;
+}
+
+fn boss_hud_system(
+    session: Res<SessionState>,
+    bosses: Query<(&Boss, &Health)>,
+    mut visibilities: Query<&mut Visibility>,
+    mut nodes: Query<&mut Node>,
+    mut texts: Query<&mut Text>,
+) {
+    let fight = bosses
+        .iter()
+        .find(|(boss, _)| boss.active && !boss.defeated);
+    if let Some(mut visibility) = session
+        .hud_boss_bar
+        .and_then(|bar| visibilities.get_mut(bar).ok())
+    {
+        *visibility = if fight.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    let Some((boss, health)) = fight else {
+        return;
+    };
+    if let Some(mut node) = session.hud_boss_fill.and_then(|fill| nodes.get_mut(fill).ok()) {
+        node.width = percent(100.0 * health.current as f32 / health.max.max(1) as f32);
+    }
+    if let Some(mut text) = session.hud_boss_name.and_then(|name| texts.get_mut(name).ok()) {
+        if text.0 != boss.name {
+            text.0 = boss.name.clone();
+        }
+    }
+}
+
+fn dart_trap_system(
+    time: Res<Time>,
+    assets: Res<GameAssets>,
+    mut commands: Commands,
+    mut traps: Query<(&Transform, &mut DartTrap)>,
+) {
+    let dt = time.delta_secs();
+    for (transform, mut trap) in traps.iter_mut() {
+        trap.timer -= dt;
+        if trap.timer > 0.0 {
+            continue;
+        }
+        trap.timer += trap.interval;
+        let origin = Vec2::new(
+            transform.translation.x + trap.direction * 6.0,
+            transform.translation.y,
+        );
+        spawn_projectile(
+            &mut commands,
+            assets.dart.clone(),
+            origin,
+            Projectile {
+                velocity: Vec2::new(trap.direction * trap.speed, 0.0),
+                gravity_scale: 0.0,
+                lifetime: 3.0,
+                owner: ProjectileOwner::Trap,
+                damage: Damage::Hit(1),
+                size: Vec2::new(5.0, 2.0),
+            },
+        );
+    }
+}
+
+fn guard_state_label_system(
+    guards: Query<&Guard>,
+    mut labels: Query<(&ChildOf, &mut Text2d), With<GuardStateLabel>>,
+) {
+    for (child_of, mut text) in labels.iter_mut() {
+        let Ok(guard) = guards.get(child_of.parent()) else {
+            continue;
+        };
+        let label = guard.state.label();
+        if text.0 != label {
+            text.0 = label.to_string();
+        }
+    }
+}
+
+fn guard_sight<F: QueryFilter>(
+    guard_pos: Vec2,
+    direction: f32,
+    player_pos: Vec2,
+    solids: &Query<(&Transform, &Collider), F>,
+) -> f32 {
+    let eye = guard_pos + Vec2::new(0.0, 6.0);
+    let offset = player_pos - eye;
+    let distance = offset.length();
+    if distance > GUARD_SIGHT_RANGE {
+        return 0.0;
+    }
+    if distance > 12.0 && offset.x * direction < distance * GUARD_VIEW_CONE_COS {
+        return 0.0;
+    }
+    let blocked = solids.iter().any(|(transform, collider)| {
+        let center = Vec2::new(transform.translation.x, transform.translation.y);
+        segment_hits_aabb(eye, player_pos, center, collider.size)
+    });
+    if blocked {
+        0.0
+    } else {
+        1.0 - 0.5 * distance / GUARD_SIGHT_RANGE
+    }
+}
+
+fn player_visibility<F: QueryFilter>(
+    player_pos: Vec2,
+    crouched: bool,
+    lights: &Query<(&Transform, &LightSource), F>,
+) -> f32 {
+    let lit: f32 = lights
+        .iter()
+        .map(|(transform, light)| {
+            let center = Vec2::new(transform.translation.x, transform.translation.y);
+            (1.0 - center.distance(player_pos) / light.radius).max(0.0)
+        })
+        .sum();
+    let light = (AMBIENT_LIGHT + lit).min(1.0);
+    if crouched {
+        light * CROUCH_VISIBILITY
+    } else {
+        light
+    }
+}
+
+fn run_timer_hud_system(
+    session: Res<SessionState>,
+    timer: Res<LevelTimer>,
+    mut texts: Query<(&mut Text, &mut TextColor)>,
+) {
+    if let Some((mut text, _)) = session.hud_timer.and_then(|entity| texts.get_mut(entity).ok()) {
+        let label = format_time(timer.elapsed);
+        if text.0 != label {
+            text.0 = label;
+        }
+    }
+    let Some(last) = timer.splits.last() else {
+        return;
+    };
+    let Some((mut text, mut color)) = session
+        .hud_split
+        .and_then(|entity| texts.get_mut(entity).ok())
+    else {
+        return;
+    };
+    let (label, tint) = match last.delta {
+        Some(delta) if delta < 0.0 => (
+            format!("{} {}", last.split.label(), format_delta(delta)),
+            Color::srgba(0.45, 0.9, 0.5, 0.95),
+        ),
+        Some(delta) => (
+            format!("{} {}", last.split.label(), format_delta(delta)),
+            Color::srgba(0.95, 0.45, 0.4, 0.95),
+        ),
+        None => (
+            format!("{} {}", last.split.label(), format_time(last.time)),
+            Color::srgba(0.9, 0.85, 0.75, 0.9),
+        ),
+    };
+    if text.0 != label {
+        text.0 = label;
+        color.0 = tint;
+    }
+}
+
+fn dagger_hud_system(
+    session: Res<SessionState>,
+    actions: Res<ActionInput>,
+    settings: Res<Settings>,
+    player_q: Query<&PlayerState, With<Player>>,
+    mut texts: Query<&mut Text>,
+) {
+    let Ok(state) = player_q.single() else {
+        return;
+    };
+    let Some(mut text) = session.hud_daggers.and_then(|entity| texts.get_mut(entity).ok()) else {
+        return;
+    };
+    let label = format!(
+        "x{} [{}]",
+        state.daggers,
+        action_prompt(&actions, &settings, Action::Throw)
+    );
+    if text.0 != label {
+        text.0 = label;
+    }
+}
+
+fn stealth_hud_system(
+    session: Res<SessionState>,
+    guards: Query<&Guard>,
+    mut eyes: Query<&mut ImageNode>,
+) {
+    let Some(mut eye) = session.hud_eye.and_then(|eye| eyes.get_mut(eye).ok()) else {
+        return;
+    };
+    let awareness = guards
+        .iter()
+        .filter(|guard| guard.alive)
+        .map(|guard| {
+            if guard.state.is_aware() {
+                1.0
+            } else {
+                guard.awareness
+            }
+        })
+        .fold(0.0, f32::max);
+    let index = if awareness >= 1.0 {
+        2
+    } else if awareness > 0.0 {
+        1
+    } else {
+        0
+    };
+    if let Some(atlas) = &mut eye.texture_atlas {
+        atlas.index = index;
+    }
+    eye.color = Color::srgb(1.0, 1.0 - 0.6 * awareness, 1.0 - 0.7 * awareness);
+}
 
-        let dir = if sprite.flip_x { -1.0 } else { 1.0 };
-        if let Ok((mut slash_tf, mut slash_sprite, _)) = slash_q.get_mut(state.slash_entity) {
-            slash_tf.translation.x = 14.0 * dir;
-            slash_tf.translation.y = 4.0;
-            slash_sprite.flip_x = sprite.flip_x;
+fn segment_hits_aabb(from: Vec2, to: Vec2, center: Vec2, size: Vec2) -> bool {
+    let half = size * 0.5;
+    let delta = to - from;
+    let mut t_min = 0.0_f32;
+    let mut t_max = 1.0_f32;
+    for (origin, dir, lo, hi) in [
+        (from.x, delta.x, center.x - half.x, center.x + half.x),
+        (from.y, delta.y, center.y - half.y, center.y + half.y),
+    ] {
+        if dir.abs() < f32::EPSILON {
+            if origin <= lo || origin >= hi {
+                return false;
+            }
+            continue;
         }
-
-        if state.attack_active > 0.0 {
-            try_hit_guard(new_pos, dir, &mut guards, &mut commands);
+        let t1 = (lo - origin) / dir;
+        let t2 = (hi - origin) / dir;
+        t_min = t_min.max(t1.min(t2));
+        t_max = t_max.min(t1.max(t2));
+        if t_min >= t_max {
+            return false;
         }
     }
+    true
 }
 
-fn guard_system(
+fn block_system(
     time: Res<Time>,
-    solids: Query<(&Transform, &Collider), (With<Solid>, Without<Guard>)>,
-    mut guards: Query<
-        (&mut Transform, &mut Sprite, &mut Guard, &mut Velocity, &Collider),
-        With<Guard>,
+    actions: Res<ActionInput>,
+    mut player_q: Query<(&Transform, &Collider, &mut PlayerState), With<Player>>,
+    mut blocks: Query<
+        (Entity, &mut Transform, &mut Velocity, &Collider, &Block),
+        Without<Player>,
     >,
+    solids: Colliders<(With<Solid>, Without<Block>, Without<Player>)>,
 ) {
     let dt = time.delta_secs();
-    for (mut transform, mut sprite, mut guard, mut velocity, collider) in guards.iter_mut() {
-        if !guard.alive {
-            continue;
-        }
+    let mut player = player_q.single_mut().ok();
+    if let Some((_, _, state)) = player.as_mut() {
+        state.pushing = false;
+        state.pulling = false;
+    }
 
-        velocity.x = guard.speed * guard.direction;
-        velocity.y += guard.gravity * dt;
+    let mut rects: Vec<(Entity, Vec2, Vec2)> = blocks
+        .iter()
+        .map(|(entity, transform, _, collider, _)| {
+            (
+                entity,
+                Vec2::new(transform.translation.x, transform.translation.y),
+                collider.size,
+            )
+        })
+        .collect();
+    rects.sort_by(|a, b| a.1.y.total_cmp(&b.1.y));
+    let order: Vec<Entity> = rects.iter().map(|(entity, _, _)| *entity).collect();
 
+    for entity in order {
+        let Ok((_, mut transform, mut velocity, collider, block)) = blocks.get_mut(entity) else {
+            continue;
+        };
         let mut pos = Vec2::new(transform.translation.x, transform.translation.y);
-        let delta = **velocity * dt;
-        let (hit_x, hit_y) = move_with_collisions(&mut pos, delta, collider.size, &solids);
-        if hit_x {
-            velocity.x = 0.0;
+        velocity.y = (velocity.y + BLOCK_GRAVITY * dt).max(-BLOCK_MAX_FALL_SPEED);
+
+        let mut dx = 0.0;
+        if let Some((player_tf, player_collider, state)) = player.as_mut() {
+            let player_pos = Vec2::new(player_tf.translation.x, player_tf.translation.y);
+            let side = (pos.x - player_pos.x).signum();
+            let reach = (player_collider.size.x + collider.size.x) * 0.5;
+            let gap = (pos.x - player_pos.x).abs() - reach;
+            let overlap_y = (pos.y - player_pos.y).abs()
+                < (player_collider.size.y + collider.size.y) * 0.5 - 2.0;
+            let input_dir = move_input(&actions);
+            let free = !state.pushing && !state.pulling;
+            if free && overlap_y && state.grounded && !state.on_ladder && gap > -1.0 {
+                if input_dir == side && gap <= 1.0 {
+                    dx = side * block.push_speed * dt;
+                    state.pushing = true;
+                    state.push_speed = block.push_speed;
+                } else if input_dir == -side && gap <= 4.0 && actions.pressed(Action::Interact) {
+                    dx = player_pos.x + side * reach - pos.x;
+                    state.pulling = true;
+                    state.push_speed = block.pull_speed;
+                }
+            }
         }
+
+        let delta = Vec2::new(dx, velocity.y * dt);
+        let others = rects
+            .iter()
+            .filter(|(other, _, _)| *other != entity)
+            .map(|(_, other_pos, other_size)| (*other_pos, *other_size))
+            .chain(solids.iter().map(|(solid_tf, solid_collider)| {
+                (
+                    Vec2::new(solid_tf.translation.x, solid_tf.translation.y),
+                    solid_collider.size,
+                )
+            }));
+        let (_, hit_y) = move_against(&mut pos, delta, collider.size, others);
         if hit_y {
             velocity.y = 0.0;
         }
-
-        if pos.x <= guard.left_limit {
-            pos.x = guard.left_limit;
-            guard.direction = 1.0;
-        } else if pos.x >= guard.right_limit {
-            pos.x = guard.right_limit;
-            guard.direction = -1.0;
+        if pos.y < -LEVEL_HEIGHT {
+            pos = block.home;
+            *velocity = Velocity(Vec2::ZERO);
         }
 
         transform.translation.x = pos.x;
         transform.translation.y = pos.y;
+        if let Some(rect) = rects.iter_mut().find(|(other, _, _)| *other == entity) {
+            rect.1 = pos;
+        }
+    }
+}
 
-        sprite.flip_x = guard.direction < 0.0;
-        guard.walk_timer += dt * 6.0;
-        if let Some(atlas) = &mut sprite.texture_atlas {
-            atlas.index = guard.walk_timer as usize % 2;
+fn pressure_plate_system(
+    mut plates: Query<(&Transform, &Collider, &mut PressurePlate, &mut Sprite)>,
+    bodies: Colliders<Or<(With<Player>, With<Block>)>>,
+    mut gates: Query<&mut Gate>,
+) {
+    for (plate_tf, plate_collider, mut plate, mut sprite) in plates.iter_mut() {
+        let plate_pos = Vec2::new(plate_tf.translation.x, plate_tf.translation.y);
+        let pressed = bodies.iter().any(|(body_tf, body_collider)| {
+            aabb_intersects(
+                Vec2::new(body_tf.translation.x, body_tf.translation.y),
+                body_collider.size,
+                plate_pos,
+                plate_collider.size,
+            )
+        });
+        if pressed == plate.pressed {
+            continue;
+        }
+        plate.pressed = pressed;
+        sprite.color = if pressed {
+            Color::srgba(0.7, 0.7, 0.7, 1.0)
+        } else {
+            Color::WHITE
+        };
+        if let Ok(mut gate) = gates.get_mut(plate.gate) {
+            gate.open = pressed;
         }
     }
 }
@@ -1524,13 +6097,22 @@ fn guard_system(
 fn key_pickup_system(
     mut commands: Commands,
     mut session: ResMut<SessionState>,
+    events: ProgressEvents,
     audio: Res<AudioAssets>,
     player_q: Query<(&Transform, &Collider), With<Player>>,
-    key_q: Query<(Entity, &Transform, &Collider), With<Key>>,
-    door_q: Query<(Entity, &Transform), With<Door>>,
-    door_blockers: Query<Entity, With<DoorBlocker>>,
-    mut hud_icons: Query<&mut ImageNode>,
+    world: KeyDoorWorld,
 ) {
+    let ProgressEvents {
+        mut splits,
+        mut collects,
+        ..
+    } = events;
+    let KeyDoorWorld {
+        key_q,
+        door_q,
+        door_blockers,
+        mut hud_icons,
+    } = world;
     if session.has_key {
         return;
     }
@@ -1544,6 +6126,8 @@ fn key_pickup_system(
         let key_pos = Vec2::new(key_tf.translation.x, key_tf.translation.y);
         if aabb_intersects(player_pos, player_collider.size, key_pos, key_collider.size) {
             session.has_key = true;
+            splits.write(SplitEvent(Split::Key));
+            collects.write(CollectEvent(KEY_ITEM));
             commands.entity(key_entity).despawn();
             play_sfx(&mut commands, audio.key.clone(), 1.0);
 
@@ -1570,25 +6154,112 @@ fn key_pickup_system(
     }
 }
 
+fn upgrade_pickup_system(
+    mut commands: Commands,
+    mut session: ResMut<SessionState>,
+    mut collects: MessageWriter<CollectEvent>,
+    audio: Res<AudioAssets>,
+    mut player_q: Query<(&Transform, &Collider, &mut PlayerState), With<Player>>,
+    upgrade_q: Query<(Entity, &Transform, &Collider), With<WallJumpUpgrade>>,
+) {
+    let Ok((player_tf, player_collider, mut state)) = player_q.single_mut() else {
+        return;
+    };
+    let player_pos = Vec2::new(player_tf.translation.x, player_tf.translation.y);
+
+    for (entity, upgrade_tf, upgrade_collider) in upgrade_q.iter() {
+        let pos = Vec2::new(upgrade_tf.translation.x, upgrade_tf.translation.y);
+        if aabb_intersects(player_pos, player_collider.size, pos, upgrade_collider.size) {
+            session.wall_jump_unlocked = true;
+            state.wall_jump_enabled = true;
+            collects.write(CollectEvent(WALL_JUMP_ITEM));
+            commands.entity(entity).despawn();
+            play_sfx(&mut commands, audio.key.clone(), 1.0);
+        }
+    }
+}
+
 fn checkpoint_system(
+    session: Res<SessionState>,
+    timer: Res<LevelTimer>,
+    events: ProgressEvents,
+    data: LevelData,
+    mut snapshot: ResMut<CheckpointSnapshot>,
     mut player_q: Query<(&Transform, &Collider, &mut PlayerState), With<Player>>,
-    checkpoint_q: Query<(&Transform, &Collider), With<Checkpoint>>,
+    world: CheckpointWorld,
 ) {
+    let ProgressEvents {
+        mut splits,
+        mut autosave,
+        ..
+    } = events;
+    let LevelData {
+        guard_spawns,
+        boss_spawns,
+        ..
+    } = data;
+    let CheckpointWorld {
+        mut checkpoint_q,
+        guards,
+        bosses,
+        blocks,
+    } = world;
     let Ok((player_tf, player_collider, mut state)) = player_q.single_mut() else {
         return;
     };
     let player_pos = Vec2::new(player_tf.translation.x, player_tf.translation.y);
 
-    for (checkpoint_tf, checkpoint_collider) in checkpoint_q.iter() {
+    for (checkpoint_tf, checkpoint_collider, mut checkpoint) in checkpoint_q.iter_mut() {
         let pos = Vec2::new(checkpoint_tf.translation.x, checkpoint_tf.translation.y);
-        if aabb_intersects(player_pos, player_collider.size, pos, checkpoint_collider.size) {
+        let inside =
+            aabb_intersects(player_pos, player_collider.size, pos, checkpoint_collider.size);
+        if inside && !checkpoint.occupied {
             state.respawn_position = to_world(Vec2::new(990.0, 170.0));
+            state.daggers = MAX_DAGGERS;
+
+            snapshot.dead_guards = guard_spawns
+                .0
+                .iter()
+                .filter(|spawn| {
+                    !guards
+                        .iter()
+                        .any(|(guard, name)| guard.alive && name.as_str() == spawn.label)
+                })
+                .map(|spawn| spawn.label.to_string())
+                .collect();
+            snapshot.defeated_bosses = boss_spawns
+                .0
+                .iter()
+                .filter(|spawn| {
+                    !bosses
+                        .iter()
+                        .any(|(boss, name)| !boss.defeated && name.as_str() == spawn.label)
+                })
+                .map(|spawn| spawn.label.to_string())
+                .collect();
+            snapshot.blocks = blocks
+                .iter()
+                .map(|(transform, name)| {
+                    (
+                        name.to_string(),
+                        Vec2::new(transform.translation.x, transform.translation.y),
+                    )
+                })
+                .collect();
+            snapshot.has_key = session.has_key;
+            snapshot.respawn = Some(state.respawn_position);
+            snapshot.time = timer.elapsed;
+            autosave.write(AutosaveEvent::Checkpoint);
+            splits.write(SplitEvent(Split::Checkpoint));
         }
+        checkpoint.occupied = inside;
     }
 }
 
 fn princess_rescue_system(
     mut next_state: ResMut<NextState<AppState>>,
+    mut autosave: MessageWriter<AutosaveEvent>,
+    mut splits: MessageWriter<SplitEvent>,
     audio: Res<AudioAssets>,
     player_q: Query<(&Transform, &Collider), With<Player>>,
     princess_q: Query<(&Transform, &Collider), With<Princess>>,
@@ -1603,6 +6274,8 @@ fn princess_rescue_system(
         let pos = Vec2::new(princess_tf.translation.x, princess_tf.translation.y);
         if aabb_intersects(player_pos, player_collider.size, pos, princess_collider.size) {
             play_sfx(&mut commands, audio.win.clone(), 1.0);
+            splits.write(SplitEvent(Split::LevelExit));
+            autosave.write(AutosaveEvent::LevelComplete);
             next_state.set(AppState::End);
             break;
         }
@@ -1610,33 +6283,32 @@ fn princess_rescue_system(
 }
 
 fn hazard_system(
-    mut respawn_writer: MessageWriter<RespawnEvent>,
-    audio: Res<AudioAssets>,
+    mut hit_writer: MessageWriter<PlayerHitEvent>,
     player_q: Query<(&Transform, &Collider), With<Player>>,
-    hazard_q: Query<(&Transform, &Collider), With<Hazard>>,
-    mut commands: Commands,
+    hazard_q: Query<(&Transform, &Collider, &Hazard)>,
 ) {
     let Ok((player_tf, player_collider)) = player_q.single() else {
         return;
     };
     let player_pos = Vec2::new(player_tf.translation.x, player_tf.translation.y);
 
-    for (hazard_tf, hazard_collider) in hazard_q.iter() {
+    for (hazard_tf, hazard_collider, hazard) in hazard_q.iter() {
         let pos = Vec2::new(hazard_tf.translation.x, hazard_tf.translation.y);
         if aabb_intersects(player_pos, player_collider.size, pos, hazard_collider.size) {
-            respawn_writer.write(RespawnEvent);
-            play_sfx(&mut commands, audio.alert.clone(), 1.0);
+            hit_writer.write(PlayerHitEvent {
+                damage: hazard.damage,
+                from: pos,
+                cause: hazard.cause,
+            });
             break;
         }
     }
 }
 
 fn guard_hit_system(
-    mut respawn_writer: MessageWriter<RespawnEvent>,
-    audio: Res<AudioAssets>,
+    mut hit_writer: MessageWriter<PlayerHitEvent>,
     player_q: Query<(&Transform, &Collider), With<Player>>,
     guards: Query<(&Transform, &Collider, &Guard)>,
-    mut commands: Commands,
 ) {
     let Ok((player_tf, player_collider)) = player_q.single() else {
         return;
@@ -1644,43 +6316,372 @@ fn guard_hit_system(
     let player_pos = Vec2::new(player_tf.translation.x, player_tf.translation.y);
 
     for (guard_tf, guard_collider, guard) in guards.iter() {
-        if !guard.alive {
+        if !guard.alive || guard.downed_timer > 0.0 {
             continue;
         }
         let guard_pos = Vec2::new(guard_tf.translation.x, guard_tf.translation.y);
         if aabb_intersects(player_pos, player_collider.size, guard_pos, guard_collider.size) {
-            respawn_writer.write(RespawnEvent);
-            play_sfx(&mut commands, audio.alert.clone(), 1.0);
+            hit_writer.write(PlayerHitEvent {
+                damage: Damage::Hit(1),
+                from: guard_pos,
+                cause: DeathCause::Guard,
+            });
             break;
         }
     }
 }
 
+fn player_damage_system(
+    mut reader: MessageReader<PlayerHitEvent>,
+    mut respawn_writer: MessageWriter<RespawnEvent>,
+    mut impact_writer: MessageWriter<ImpactEvent>,
+    audio: Res<AudioAssets>,
+    mut player_q: Query<
+        (Entity, &Transform, &mut Velocity, &mut PlayerState, &mut Health),
+        With<Player>,
+    >,
+    mut commands: Commands,
+) {
+    let Ok((entity, transform, mut velocity, mut state, mut health)) = player_q.single_mut()
+    else {
+        reader.clear();
+        return;
+    };
+    let pos = Vec2::new(transform.translation.x, transform.translation.y);
+
+    for hit in reader.read() {
+        if health.current == 0 {
+            continue;
+        }
+        match hit.damage {
+            Damage::Kill => health.current = 0,
+            Damage::Hit(amount) => {
+                if state.invincible_timer > 0.0 {
+                    continue;
+                }
+                health.current = health.current.saturating_sub(amount);
+            }
+        }
+
+        let dir = if pos.x < hit.from.x { -1.0 } else { 1.0 };
+        if health.current == 0 {
+            respawn_writer.write(RespawnEvent {
+                cause: Some(hit.cause),
+            });
+            impact_writer.write(ImpactEvent {
+                kind: ImpactKind::Death,
+                pos,
+                dir: 0.0,
+                target: None,
+            });
+            play_sfx(&mut commands, audio.alert.clone(), 1.0);
+            continue;
+        }
+
+        impact_writer.write(ImpactEvent {
+            kind: ImpactKind::Hurt,
+            pos,
+            dir,
+            target: Some(entity),
+        });
+
+        if hit.from.x != pos.x {
+            velocity.x = dir * state.knockback.x;
+            velocity.y = state.knockback.y;
+            state.on_ladder = false;
+            state.jump_held = false;
+            state.hurt_timer = 0.25;
+        }
+        state.invincible_timer = state.invincible_time;
+        play_sfx(&mut commands, audio.hurt.clone(), db_to_linear(-3.0));
+    }
+}
+
+fn health_hud_system(
+    session: Res<SessionState>,
+    player_q: Query<&Health, (With<Player>, Changed<Health>)>,
+    mut hearts: Query<&mut ImageNode>,
+) {
+    let Ok(health) = player_q.single() else {
+        return;
+    };
+    for (index, heart_entity) in session.hud_hearts.iter().enumerate() {
+        if let Ok(mut heart) = hearts.get_mut(*heart_entity) {
+            heart.color = if (index as u32) < health.current {
+                Color::WHITE
+            } else {
+                Color::srgba(0.25, 0.2, 0.2, 0.6)
+            };
+        }
+    }
+}
+
+fn resume_save_system(
+    mut saves: ResMut<SaveSlots>,
+    mut snapshot: ResMut<CheckpointSnapshot>,
+    mut timer: ResMut<LevelTimer>,
+    mut player_q: Query<&mut PlayerState, With<Player>>,
+    mut respawn_writer: MessageWriter<RespawnEvent>,
+) {
+    if !saves.resume {
+        return;
+    }
+    let Ok(mut state) = player_q.single_mut() else {
+        return;
+    };
+    saves.resume = false;
+    let Some(checkpoint) = saves.current().and_then(|data| data.checkpoint.clone()) else {
+        return;
+    };
+    if let Some(respawn) = checkpoint.respawn {
+        state.respawn_position = respawn;
+    }
+    timer.elapsed = checkpoint.time;
+    timer.resumed = true;
+    *snapshot = checkpoint;
+    respawn_writer.write(RespawnEvent { cause: None });
+}
+
+fn autosave_system(
+    mut reader: MessageReader<AutosaveEvent>,
+    mut saves: ResMut<SaveSlots>,
+    session: Res<SessionState>,
+    snapshot: Res<CheckpointSnapshot>,
+    timer: Res<LevelTimer>,
+) {
+    for event in reader.read() {
+        let slot = saves.selected;
+        let Some(data) = saves.slots.get_mut(slot).and_then(|slot| slot.data.as_mut()) else {
+            continue;
+        };
+        if session.wall_jump_unlocked {
+            data.collected.insert(WALL_JUMP_ITEM.to_string());
+        }
+        match event {
+            AutosaveEvent::Checkpoint => data.checkpoint = Some(snapshot.clone()),
+            AutosaveEvent::LevelComplete => {
+                data.checkpoint = None;
+                if !timer.resumed {
+                    let best = data.best_times.entry(data.level.clone()).or_insert(timer.elapsed);
+                    *best = best.min(timer.elapsed);
+                }
+                let next = LEVELS
+                    .iter()
+                    .position(|level| *level == data.level)
+                    .and_then(|index| LEVELS.get(index + 1));
+                if let Some(next) = next {
+                    data.unlocked_levels.insert(next.to_string());
+                    data.level = next.to_string();
+                }
+            }
+        }
+        write_save(slot, data);
+    }
+}
+
+fn run_stats_system(
+    mut respawns: MessageReader<RespawnEvent>,
+    mut defeats: MessageReader<GuardDefeatedEvent>,
+    mut noises: MessageReader<NoiseEvent>,
+    mut collects: MessageReader<CollectEvent>,
+    mut stats: ResMut<RunStats>,
+) {
+    for cause in respawns.read().filter_map(|respawn| respawn.cause) {
+        *stats.deaths.entry(cause).or_default() += 1;
+    }
+    stats.guards_defeated += defeats.read().count() as u32;
+    for noise in noises.read() {
+        if noise.kind == NoiseKind::Jump {
+            stats.jumps += 1;
+        }
+    }
+    for CollectEvent(item) in collects.read() {
+        stats.collected.insert(item);
+    }
+}
+
+fn export_run_stats(
+    stats: Res<RunStats>,
+    timer: Res<LevelTimer>,
+    saves: Res<SaveSlots>,
+    tuning: Res<ResultsTuning>,
+) {
+    let Some(dir) = config_dir().map(|dir| dir.join(RUN_STATS_DIR)) else {
+        warn!("Could not export run stats, no config directory found");
+        return;
+    };
+    let finished = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let level = saves.current().map_or(LEVELS[0], |data| data.level.as_str());
+    let export = run_stats_export(&stats, &timer, &tuning, level, saves.selected, finished);
+    let json = match serde_json::to_string_pretty(&export) {
+        Ok(json) => json,
+        Err(err) => {
+            warn!("Could not serialize run stats: {}", err);
+            return;
+        }
+    };
+    let path = dir.join(format!("run-{}.json", finished));
+    let result = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, json));
+    match result {
+        Ok(()) => info!("Exported run stats to {}", path.display()),
+        Err(err) => warn!("Could not export run stats to {}: {}", path.display(), err),
+    }
+}
+
+fn run_stats_export<'a>(
+    stats: &'a RunStats,
+    timer: &LevelTimer,
+    tuning: &'a ResultsTuning,
+    level: &'a str,
+    slot: usize,
+    finished: u64,
+) -> RunStatsExport<'a> {
+    let score = tuning.score(stats, timer);
+    RunStatsExport {
+        level,
+        slot: slot + 1,
+        finished_at: finished,
+        time: timer.elapsed,
+        resumed: timer.resumed,
+        personal_best: timer.new_best,
+        deaths: DeathsExport {
+            total: stats.total_deaths(),
+            causes: DeathCause::ALL
+                .iter()
+                .map(|cause| (cause.key(), stats.deaths.get(cause).copied().unwrap_or(0)))
+                .collect(),
+        },
+        guards_defeated: stats.guards_defeated,
+        jumps: stats.jumps,
+        collectibles: CollectiblesExport {
+            collected: stats.collected.iter().copied().collect(),
+            total: COLLECTIBLES.len(),
+        },
+        splits: timer
+            .splits
+            .iter()
+            .map(|time| SplitExport {
+                split: time.split.label(),
+                time: time.time,
+                delta: time.delta,
+            })
+            .collect(),
+        score,
+        grade: tuning.grade(score),
+    }
+}
+
+fn level_timer_system(time: Res<Time>, mut timer: ResMut<LevelTimer>) {
+    if !timer.finished {
+        timer.elapsed += time.delta_secs();
+    }
+}
+
+fn split_system(
+    mut reader: MessageReader<SplitEvent>,
+    mut timer: ResMut<LevelTimer>,
+    mut bests: ResMut<PersonalBests>,
+    saves: Res<SaveSlots>,
+) {
+    let level = saves
+        .current()
+        .map_or(LEVELS[0], |data| data.level.as_str())
+        .to_string();
+    for SplitEvent(split) in reader.read() {
+        if timer.finished || timer.splits.iter().any(|time| time.split == *split) {
+            continue;
+        }
+        let best = bests.levels.get(&level);
+        let delta = best
+            .and_then(|best| best.splits.get(split))
+            .map(|time| timer.elapsed - time);
+        let best_total = best.map(|best| best.total);
+        let elapsed = timer.elapsed;
+        timer.splits.push(SplitTime {
+            split: *split,
+            time: elapsed,
+            delta,
+        });
+        if *split != Split::LevelExit {
+            continue;
+        }
+        timer.finished = true;
+        timer.best = best_total;
+        if !timer.resumed && best_total.is_none_or(|best| elapsed < best) {
+            timer.new_best = true;
+            let splits = timer.splits.iter().map(|time| (time.split, time.time)).collect();
+            bests.levels.insert(
+                level.clone(),
+                PersonalBest {
+                    total: elapsed,
+                    splits,
+                },
+            );
+            save_personal_bests(&bests);
+        }
+    }
+}
+
 fn respawn_system(
     mut reader: MessageReader<RespawnEvent>,
-    mut player_q: Query<(&mut Transform, &mut Velocity, &mut PlayerState), With<Player>>,
-    mut guards: Query<Entity, With<Guard>>,
+    mut player_q: RespawnedPlayer,
+    world: RespawnWorld,
     mut commands: Commands,
-    assets: Res<GameAssets>,
-    atlases: Res<AtlasAssets>,
-    guard_spawns: Res<GuardSpawns>,
-    ui: Res<UiAssets>,
-    labels: Res<LabelSettings>,
-    mut slash_q: Query<&mut Visibility, (With<Slash>, Without<Player>)>,
+    level: LevelAssets,
+    data: LevelData,
+    snapshot: Res<CheckpointSnapshot>,
 ) {
+    let RespawnWorld {
+        mut guards,
+        mut blocks,
+        mut slash_q,
+    } = world;
+    let LevelData {
+        guard_spawns,
+        archetypes,
+        rules,
+        movement_tuning,
+        ..
+    } = data;
     if reader.read().next().is_none() {
         return;
     }
 
-    if let Ok((mut transform, mut velocity, mut state)) = player_q.single_mut() {
+    if let Ok((mut transform, mut velocity, mut state, mut collider, mut health)) =
+        player_q.single_mut()
+    {
+        health.current = health.max;
         transform.translation.x = state.respawn_position.x;
         transform.translation.y = state.respawn_position.y;
         *velocity = Velocity(Vec2::ZERO);
         state.on_ladder = false;
+        state.wall_dir = 0.0;
+        state.wall_jump_lock_timer = 0.0;
         state.coyote_timer = state.coyote_time;
         state.jump_buffer_timer = 0.0;
-        state.attack_active = 0.0;
-        state.attack_cooldown = 0.0;
+        state.jump_held = false;
+        state.grounded = false;
+        state.fall_apex_y = state.respawn_position.y;
+        state.land_stun_timer = 0.0;
+        state.crouched = false;
+        collider.size.y = state.stand_height;
+        state.pushing = false;
+        state.pulling = false;
+        state.in_water = false;
+        state.air = state.air_time;
+        state.hurt_timer = 0.0;
+        state.invincible_timer = 0.0;
+        state.attack = None;
+        state.attack_windup = 0.0;
+        state.attack_hitbox = None;
+        state.attack_buffer = 0.0;
+        state.combo_step = 0;
+        state.combo_timer = 0.0;
+        state.parry_timer = 0.0;
+        state.stagger_timer = 0.0;
+        state.daggers = MAX_DAGGERS;
         if let Ok(mut visibility) = slash_q.get_mut(state.slash_entity) {
             *visibility = Visibility::Hidden;
         }
@@ -1690,7 +6691,124 @@ fn respawn_system(
         commands.entity(entity).despawn();
     }
 
-    spawn_guards(&mut commands, &assets, &atlases, &guard_spawns, &ui, &labels);
+    for (mut transform, mut velocity, block, name) in blocks.iter_mut() {
+        let pos = snapshot
+            .blocks
+            .iter()
+            .find(|(label, _)| label.as_str() == name.as_str())
+            .map_or(block.home, |(_, pos)| *pos);
+        transform.translation.x = pos.x;
+        transform.translation.y = pos.y;
+        *velocity = Velocity(Vec2::ZERO);
+    }
+
+    let survivors = GuardSpawns(
+        guard_spawns
+            .0
+            .iter()
+            .filter(|spawn| !snapshot.dead_guards.iter().any(|label| label == spawn.label))
+            .copied()
+            .collect(),
+    );
+    let preset = movement_tuning.preset(&rules.tuning_preset);
+    spawn_guards(
+        &mut commands,
+        &level,
+        &survivors,
+        &archetypes,
+        &preset.guard,
+    );
+}
+
+fn restore_checkpoint_system(
+    mut reader: MessageReader<RespawnEvent>,
+    mut commands: Commands,
+    mut session: ResMut<SessionState>,
+    snapshot: Res<CheckpointSnapshot>,
+    level: LevelAssets,
+    data: LevelData,
+    world: RestoreWorld,
+) {
+    let LevelAssets {
+        assets,
+        atlases,
+        ui,
+        labels,
+        ..
+    } = &level;
+    let LevelData {
+        boss_spawns,
+        bosses,
+        ..
+    } = data;
+    let RestoreWorld {
+        keys,
+        door_blockers,
+        mut doors,
+        arena,
+        mut hud_icons,
+    } = world;
+    if reader.read().next().is_none() {
+        return;
+    }
+
+    if session.has_key && !snapshot.has_key {
+        session.has_key = false;
+        for entity in keys.iter() {
+            commands.entity(entity).despawn();
+        }
+        spawn_key(&mut commands, assets, ui, labels);
+        for (entity, mut transform, door) in doors.iter_mut() {
+            commands.entity(entity).remove::<DoorOpening>();
+            transform.translation.y = door.closed_y;
+            spawn_door_blocker(
+                &mut commands,
+                Vec2::new(transform.translation.x, door.closed_y),
+            );
+        }
+        if let Some(mut icon) = session
+            .hud_key_icon
+            .and_then(|icon| hud_icons.get_mut(icon).ok())
+        {
+            icon.color = Color::srgba(0.5, 0.5, 0.5, 0.8);
+        }
+    } else if !session.has_key && snapshot.has_key {
+        session.has_key = true;
+        for entity in keys.iter().chain(door_blockers.iter()) {
+            commands.entity(entity).despawn();
+        }
+        for (entity, mut transform, door) in doors.iter_mut() {
+            commands.entity(entity).remove::<DoorOpening>();
+            transform.translation.y = door.closed_y + DOOR_OPEN_OFFSET;
+        }
+        if let Some(mut icon) = session
+            .hud_key_icon
+            .and_then(|icon| hud_icons.get_mut(icon).ok())
+        {
+            icon.color = Color::WHITE;
+        }
+    }
+
+    for entity in arena.iter() {
+        commands.entity(entity).despawn();
+    }
+    let remaining = BossSpawns(
+        boss_spawns
+            .0
+            .iter()
+            .filter(|spawn| !snapshot.defeated_bosses.iter().any(|label| label == spawn.label))
+            .copied()
+            .collect(),
+    );
+    spawn_bosses(
+        &mut commands,
+        assets,
+        atlases,
+        &remaining,
+        &bosses,
+        ui,
+        labels,
+    );
 }
 
 fn door_open_system(
@@ -1710,20 +6828,133 @@ fn door_open_system(
     }
 }
 
-fn fade_out_system(
+fn gate_system(time: Res<Time>, mut gates: Query<(&mut Transform, &Gate)>) {
+    let step = GATE_SPEED * time.delta_secs();
+    for (mut transform, gate) in gates.iter_mut() {
+        let target = if gate.open { gate.open_y } else { gate.closed_y };
+        let y = transform.translation.y;
+        transform.translation.y = y + (target - y).clamp(-step, step);
+    }
+}
+
+fn fade_out_system(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Sprite, &mut FadeOut)>,
+) {
+    let dt = time.delta();
+    for (entity, mut sprite, mut fade) in query.iter_mut() {
+        fade.timer.tick(dt);
+        let t = fade.timer.elapsed_secs() / fade.timer.duration().as_secs_f32();
+        let alpha = (1.0 - t).clamp(0.0, 1.0);
+        sprite.color.set_alpha(alpha);
+        if fade.timer.is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn particle_system(time: Res<Time>, mut particles: Query<(&mut Transform, &mut Particle)>) {
+    let dt = time.delta_secs();
+    for (mut transform, mut particle) in particles.iter_mut() {
+        particle.velocity.y += particle.gravity * dt;
+        transform.translation.x += particle.velocity.x * dt;
+        transform.translation.y += particle.velocity.y * dt;
+    }
+}
+
+fn impact_feedback_system(
+    mut reader: MessageReader<ImpactEvent>,
+    tuning: Res<FeedbackTuning>,
+    accessibility: Res<AccessibilitySettings>,
+    mut hit_stop: ResMut<HitStop>,
+    mut shake: ResMut<CameraShake>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut commands: Commands,
+) {
+    if !accessibility.screen_effects {
+        reader.clear();
+        return;
+    }
+    for impact in reader.read() {
+        let effect = tuning.impact(impact.kind);
+        let (r, g, b) = effect.color;
+        let color = Color::srgb(r, g, b);
+
+        let stop = effect.hit_stop * tuning.hit_stop_scale;
+        if stop > hit_stop.remaining {
+            hit_stop.remaining = stop;
+            virtual_time.set_relative_speed(0.0);
+        }
+        shake.trauma = (shake.trauma + effect.shake * tuning.shake_scale).min(1.0);
+
+        let flash = effect.flash * tuning.flash_scale;
+        if let (Some(target), true) = (impact.target, flash > 0.0) {
+            commands.entity(target).try_insert(HitFlash {
+                timer: Timer::from_seconds(flash, TimerMode::Once),
+                color,
+            });
+        }
+
+        let count = (effect.particles as f32 * tuning.particle_scale).round() as u32;
+        for index in 0..count {
+            let t = (index as f32 + 0.5) / count as f32;
+            let angle = if impact.dir == 0.0 {
+                t * std::f32::consts::TAU
+            } else if impact.dir > 0.0 {
+                (t - 0.5) * 1.6
+            } else {
+                std::f32::consts::PI - (t - 0.5) * 1.6
+            };
+            let speed = effect.particle_speed * (0.6 + 0.1 * ((index * 7) % 5) as f32);
+            commands.spawn((
+                Sprite {
+                    color,
+                    custom_size: Some(Vec2::splat(2.0)),
+                    ..default()
+                },
+                Transform::from_xyz(impact.pos.x, impact.pos.y, Z_ACTOR + 1.0),
+                Particle {
+                    velocity: Vec2::from_angle(angle) * speed + Vec2::new(0.0, 30.0),
+                    gravity: -300.0,
+                },
+                FadeOut {
+                    timer: Timer::from_seconds(0.35, TimerMode::Once),
+                },
+                DespawnOnExit(AppState::InGame),
+            ));
+        }
+    }
+}
+
+fn hit_stop_system(
+    real_time: Res<Time<Real>>,
+    mut hit_stop: ResMut<HitStop>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    if hit_stop.remaining <= 0.0 {
+        return;
+    }
+    hit_stop.remaining -= real_time.delta_secs();
+    if hit_stop.remaining <= 0.0 {
+        hit_stop.remaining = 0.0;
+        virtual_time.set_relative_speed(1.0);
+    }
+}
+
+fn hit_flash_system(
     time: Res<Time>,
     mut commands: Commands,
-    mut query: Query<(Entity, &mut Sprite, &mut FadeOut)>,
+    mut flashes: Query<(Entity, &mut Sprite, &mut HitFlash)>,
 ) {
-    let dt = time.delta();
-    for (entity, mut sprite, mut fade) in query.iter_mut() {
-        fade.timer.tick(dt);
-        let t = fade.timer.elapsed_secs() / fade.timer.duration().as_secs_f32();
-        let alpha = (1.0 - t).clamp(0.0, 1.0);
-        sprite.color.set_alpha(alpha);
-        if fade.timer.is_finished() {
-            commands.entity(entity).despawn();
+    for (entity, mut sprite, mut flash) in flashes.iter_mut() {
+        flash.timer.tick(time.delta());
+        if flash.timer.is_finished() {
+            commands.entity(entity).remove::<HitFlash>();
+            continue;
         }
+        let alpha = sprite.color.alpha();
+        sprite.color = flash.color.with_alpha(alpha);
     }
 }
 
@@ -1735,6 +6966,13 @@ fn animate_key_system(time: Res<Time>, mut keys: Query<(&mut Transform, &mut Key
     }
 }
 
+fn animate_blade_traps_system(time: Res<Time>, mut blades: Query<(&mut Transform, &BladeTrap)>) {
+    let t = time.elapsed_secs();
+    for (mut transform, blade) in blades.iter_mut() {
+        transform.translation.x = blade.base_x + (t * 2.2 + blade.phase).sin() * blade.range;
+    }
+}
+
 fn animate_princess_system(time: Res<Time>, mut sprites: Query<(&mut Sprite, &mut PrincessWave)>) {
     let dt = time.delta_secs();
     for (mut sprite, mut wave) in sprites.iter_mut() {
@@ -1758,6 +6996,8 @@ fn animate_torches_system(time: Res<Time>, mut torches: Query<(&mut Sprite, &Tor
 
 fn camera_follow_system(
     time: Res<Time>,
+    tuning: Res<FeedbackTuning>,
+    mut shake: ResMut<CameraShake>,
     player_q: Query<&Transform, With<Player>>,
     mut camera_q: Query<&mut Transform, (With<GameCamera>, Without<Player>)>,
 ) {
@@ -1774,11 +7014,18 @@ fn camera_follow_system(
     target.x = target.x.clamp(half_w, LEVEL_WIDTH - half_w);
     target.y = target.y.clamp(half_h, LEVEL_HEIGHT - half_h);
 
-    let current = Vec2::new(camera_tf.translation.x, camera_tf.translation.y);
+    let current = Vec2::new(camera_tf.translation.x, camera_tf.translation.y) - shake.offset;
     let t = 1.0 - (-6.0_f32 * time.delta_secs()).exp();
     let lerped = current.lerp(target, t);
-    camera_tf.translation.x = lerped.x;
-    camera_tf.translation.y = lerped.y;
+
+    let dt = time.delta_secs();
+    shake.trauma = (shake.trauma - tuning.shake_decay * dt).max(0.0);
+    shake.time += dt;
+    let amount = shake.trauma * shake.trauma * tuning.max_shake;
+    shake.offset = Vec2::new((shake.time * 37.0).sin(), (shake.time * 29.0 + 1.3).sin()) * amount;
+
+    camera_tf.translation.x = lerped.x + shake.offset.x;
+    camera_tf.translation.y = lerped.y + shake.offset.y;
 }
 
 fn create_low_res_target(images: &mut Assets<Image>) -> Handle<Image> {
@@ -1800,8 +7047,8 @@ fn create_low_res_target(images: &mut Assets<Image>) -> Handle<Image> {
     images.add(image)
 }
 
-fn render_mode_from_args() -> RenderMode {
-    let mut mode = RenderMode::Cpu;
+fn render_mode_from_args(default: RenderMode) -> RenderMode {
+    let mut mode = default;
     if let Ok(value) = std::env::var("POO_RENDER_MODE") {
         let value = value.to_lowercase();
         if value == "gpu" || value == "high" || value == "hardware" {
@@ -1822,8 +7069,8 @@ fn render_mode_from_args() -> RenderMode {
     mode
 }
 
-fn render_backend_from_args() -> RenderBackend {
-    let mut backend = RenderBackend::Gl;
+fn render_backend_from_args(default: RenderBackend) -> RenderBackend {
+    let mut backend = default;
     if let Ok(value) = std::env::var("POO_WGPU_BACKEND") {
         let value = value.to_lowercase();
         match value.as_str() {
@@ -1848,6 +7095,351 @@ fn render_backend_from_args() -> RenderBackend {
     backend
 }
 
+fn tuning_reload_system(
+    time: Res<Time<Real>>,
+    mut watcher: ResMut<TuningWatcher>,
+    mut tuning: ResMut<MovementTuning>,
+    rules: Res<LevelRules>,
+    mut players: Query<&mut PlayerState>,
+    mut guards: Query<&mut Guard>,
+) {
+    if !watcher.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let modified = file_modified(&asset_file_path(MOVEMENT_TUNING_PATH));
+    if modified == watcher.modified {
+        return;
+    }
+    watcher.modified = modified;
+    match read_tuning(MOVEMENT_TUNING_PATH) {
+        Ok(reloaded) => *tuning = reloaded,
+        Err(err) => {
+            warn!("Keeping the previous movement tuning, {}", err);
+            return;
+        }
+    }
+    info!("Reloaded movement tuning from {}", MOVEMENT_TUNING_PATH);
+
+    let preset = tuning.preset(&rules.tuning_preset);
+    for mut state in players.iter_mut() {
+        apply_player_tuning(&mut state, &preset.player);
+    }
+    for mut guard in guards.iter_mut() {
+        guard.speed = preset.guard.speed;
+        guard.gravity = preset.guard.gravity;
+        guard.climb_speed = preset.guard.climb_speed;
+    }
+}
+
+fn apply_player_tuning(state: &mut PlayerState, tuning: &PlayerTuning) {
+    state.speed = tuning.speed;
+    state.jump_velocity = tuning.jump_velocity;
+    state.gravity = tuning.gravity;
+    state.coyote_time = tuning.coyote_time;
+    state.jump_buffer = tuning.jump_buffer;
+    state.climb_speed = tuning.climb_speed;
+    state.jump_cut = tuning.jump_cut;
+    state.fall_gravity_scale = tuning.fall_gravity_scale;
+    state.apex_gravity_scale = tuning.apex_gravity_scale;
+    state.apex_threshold = tuning.apex_threshold;
+    state.max_fall_speed = tuning.max_fall_speed;
+    state.swim_speed = tuning.swim_speed;
+    state.swim_stroke = tuning.swim_stroke;
+    state.air_time = tuning.air_time;
+    state.air = state.air.min(state.air_time);
+}
+
+fn file_modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn load_tuning<T: DeserializeOwned + Default>(path: &str) -> T {
+    read_tuning(path).unwrap_or_else(|err| {
+        warn!("Using default tuning, {}", err);
+        T::default()
+    })
+}
+
+fn read_tuning<T: DeserializeOwned>(path: &str) -> Result<T, String> {
+    let path = asset_file_path(path);
+    let text = std::fs::read_to_string(&path)
+        .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+    ron::from_str(&text).map_err(|err| format!("could not parse {}: {}", path.display(), err))
+}
+
+fn config_dir() -> Option<PathBuf> {
+    let dir = if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME") {
+        PathBuf::from(dir)
+    } else if cfg!(windows) {
+        PathBuf::from(std::env::var_os("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(std::env::var_os("HOME")?).join("Library/Application Support")
+    } else {
+        PathBuf::from(std::env::var_os("HOME")?).join(".config")
+    };
+    Some(dir.join(SETTINGS_DIR))
+}
+
+fn settings_path() -> Option<PathBuf> {
+    Some(config_dir()?.join(SETTINGS_FILE))
+}
+
+fn save_path(slot: usize) -> Option<PathBuf> {
+    Some(config_dir()?.join(format!("save{}.ron", slot + 1)))
+}
+
+fn write_ron_file<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let text = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())?;
+    path.parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(path, text))
+        .map_err(|err| err.to_string())
+}
+
+fn load_settings() -> Settings {
+    let Some(path) = settings_path() else {
+        return Settings::default();
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(_) => return Settings::default(),
+    };
+    match ron::from_str::<Settings>(&text) {
+        Ok(mut settings) => {
+            fill_default_bindings(&mut settings);
+            settings
+        }
+        Err(err) => {
+            warn!("Using default settings, could not parse {}: {}", path.display(), err);
+            Settings::default()
+        }
+    }
+}
+
+fn fill_default_bindings(settings: &mut Settings) {
+    for (action, keys) in default_bindings() {
+        settings.bindings.entry(action).or_insert(keys);
+    }
+    for (action, buttons) in default_gamepad_bindings() {
+        settings.gamepad_bindings.entry(action).or_insert(buttons);
+    }
+}
+
+fn save_settings(settings: &Settings) {
+    let Some(path) = settings_path() else {
+        warn!("Could not save settings, no config directory found");
+        return;
+    };
+    if let Err(err) = write_ron_file(&path, settings) {
+        warn!("Could not save settings to {}: {}", path.display(), err);
+    }
+}
+
+fn personal_bests_path() -> Option<PathBuf> {
+    Some(config_dir()?.join(PERSONAL_BESTS_FILE))
+}
+
+fn load_personal_bests(mut commands: Commands) {
+    let Some(path) = personal_bests_path() else {
+        commands.insert_resource(PersonalBests::default());
+        return;
+    };
+    let bests = match std::fs::read_to_string(&path) {
+        Ok(text) => ron::from_str::<PersonalBests>(&text).unwrap_or_else(|err| {
+            warn!("Ignoring personal bests, could not parse {}: {}", path.display(), err);
+            PersonalBests::default()
+        }),
+        Err(_) => PersonalBests::default(),
+    };
+    commands.insert_resource(bests);
+}
+
+fn save_personal_bests(bests: &PersonalBests) {
+    let Some(path) = personal_bests_path() else {
+        warn!("Could not save personal bests, no config directory found");
+        return;
+    };
+    if let Err(err) = write_ron_file(&path, bests) {
+        warn!("Could not save personal bests to {}: {}", path.display(), err);
+    }
+}
+
+fn load_save_slots(mut commands: Commands) {
+    commands.insert_resource(SaveSlots {
+        selected: 0,
+        slots: (0..SAVE_SLOTS).map(load_save).collect(),
+        resume: false,
+    });
+}
+
+fn load_save(slot: usize) -> SaveSlot {
+    let Some(path) = save_path(slot) else {
+        return SaveSlot::default();
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return SaveSlot::default(),
+        Err(err) => {
+            let error = format!("could not read {}: {}", path.display(), err);
+            error!("Save slot {} is unavailable, {}", slot + 1, error);
+            return SaveSlot {
+                data: None,
+                error: Some(error),
+            };
+        }
+    };
+    let error = match ron::from_str::<SaveData>(&text) {
+        Ok(data) if data.version <= SAVE_VERSION => {
+            return SaveSlot {
+                data: Some(migrate_save(data, slot)),
+                error: None,
+            };
+        }
+        Ok(data) => format!(
+            "{} was written by a newer version (save version {}, supported {})",
+            path.display(),
+            data.version,
+            SAVE_VERSION
+        ),
+        Err(err) => format!("{} is corrupt: {}", path.display(), err),
+    };
+    let backup = path.with_extension("ron.bak");
+    match std::fs::rename(&path, &backup) {
+        Ok(()) => error!(
+            "Save slot {} starts empty, {}. The file was moved to {}",
+            slot + 1,
+            error,
+            backup.display()
+        ),
+        Err(err) => error!(
+            "Save slot {} starts empty, {}. It could not be backed up: {}",
+            slot + 1,
+            error,
+            err
+        ),
+    }
+    SaveSlot {
+        data: None,
+        error: Some(error),
+    }
+}
+
+fn migrate_save(mut data: SaveData, slot: usize) -> SaveData {
+    let migrated = data.version < SAVE_VERSION;
+    if migrated {
+        info!(
+            "Migrating save slot {} from version {} to {}",
+            slot + 1,
+            data.version,
+            SAVE_VERSION
+        );
+        if data.version < 2 {
+            info!(
+                "Save slot {} drops its copy of the settings, settings.ron is used instead",
+                slot + 1
+            );
+        }
+        data.version = SAVE_VERSION;
+    }
+    if !LEVELS.contains(&data.level.as_str()) {
+        warn!("Save slot {} names unknown level {}, starting over", slot + 1, data.level);
+        data.level = LEVELS[0].to_string();
+        data.checkpoint = None;
+    }
+    data.unlocked_levels.retain(|level| LEVELS.contains(&level.as_str()));
+    data.unlocked_levels.insert(data.level.clone());
+    if migrated {
+        write_save(slot, &data);
+    }
+    data
+}
+
+fn write_save(slot: usize, data: &SaveData) {
+    let Some(path) = save_path(slot) else {
+        warn!("Could not save the game, no config directory found");
+        return;
+    };
+    if let Err(err) = write_ron_file(&path, data) {
+        warn!("Could not save the game to {}: {}", path.display(), err);
+    }
+}
+
+fn format_delta(delta: f32) -> String {
+    let sign = if delta < 0.0 { "-" } else { "+" };
+    if delta.abs() >= 60.0 {
+        format!("{}{}", sign, format_time(delta.abs()))
+    } else {
+        format!("{}{:.2}", sign, delta.abs())
+    }
+}
+
+fn results_text(stats: &RunStats, timer: &LevelTimer, tuning: &ResultsTuning) -> String {
+    let score = tuning.score(stats, timer);
+    let deaths = stats
+        .deaths
+        .iter()
+        .map(|(cause, count)| format!("{} {}", cause.label(), count))
+        .collect::<Vec<_>>();
+    let deaths = if deaths.is_empty() {
+        "Deaths 0".to_string()
+    } else {
+        format!("Deaths {} ({})", stats.total_deaths(), deaths.join(", "))
+    };
+    format!(
+        "Grade {}  (score {:.0})\n{}\nGuards defeated {}   Jumps {}   Collectibles {}/{}\n\n{}",
+        tuning.grade(score),
+        score,
+        deaths,
+        stats.guards_defeated,
+        stats.jumps,
+        stats.collected.len(),
+        COLLECTIBLES.len(),
+        run_summary(timer)
+    )
+}
+
+fn run_summary(timer: &LevelTimer) -> String {
+    let mut lines: Vec<String> = timer
+        .splits
+        .iter()
+        .map(|time| match time.delta {
+            Some(delta) => format!(
+                "{}  {}  ({})",
+                time.split.label(),
+                format_time(time.time),
+                format_delta(delta)
+            ),
+            None => format!("{}  {}", time.split.label(), format_time(time.time)),
+        })
+        .collect();
+    lines.push(String::new());
+    lines.push(format!("Time {}", format_time(timer.elapsed)));
+    if timer.new_best {
+        lines.push("New personal best!".to_string());
+    } else if let Some(best) = timer.best {
+        lines.push(format!("Personal best {}", format_time(best)));
+    }
+    if timer.resumed {
+        lines.push("Continued from a save, not eligible for a personal best.".to_string());
+    }
+    lines.join("\n")
+}
+
+fn format_time(seconds: f32) -> String {
+    let hundredths = (seconds.max(0.0) * 100.0).round() as u32;
+    format!(
+        "{}:{:02}.{:02}",
+        hundredths / 6000,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
+
+fn asset_file_path(path: &str) -> PathBuf {
+    FileAssetReader::get_base_path().join(ASSET_ROOT).join(path)
+}
+
 fn labels_enabled_from_args() -> bool {
     for arg in std::env::args().skip(1) {
         if arg == "--labels" {
@@ -1857,63 +7449,132 @@ fn labels_enabled_from_args() -> bool {
     false
 }
 
+fn screen_effects_from_args() -> bool {
+    for arg in std::env::args().skip(1) {
+        if arg == "--no-screen-effects" {
+            return false;
+        }
+    }
+    true
+}
+
 fn try_hit_guard<F: QueryFilter>(
-    player_pos: Vec2,
+    (sword_pos, sword_size): (Vec2, Vec2),
     dir: f32,
-    guards: &mut Query<(Entity, &Transform, &mut Guard, &Collider, &mut Velocity), F>,
+    damage: u32,
+    guards: &mut GuardTargets<F>,
     commands: &mut Commands,
-) {
-    let sword_pos = player_pos + Vec2::new(12.0 * dir, -4.0);
-    let sword_size = Vec2::new(18.0, 10.0);
+    impacts: &mut MessageWriter<ImpactEvent>,
+) -> SwordHit {
+    let mut result = SwordHit::Miss;
 
-    for (entity, guard_tf, mut guard, collider, mut velocity) in guards.iter_mut() {
-        if !guard.alive {
+    for (entity, guard_tf, mut guard, collider, mut velocity, mut health) in guards.iter_mut() {
+        if !guard.alive || guard.downed_timer > 0.0 {
             continue;
         }
         let guard_pos = Vec2::new(guard_tf.translation.x, guard_tf.translation.y);
-        if aabb_intersects(sword_pos, sword_size, guard_pos, collider.size)
-            || guard_pos.distance(player_pos) <= 26.0
-        {
-            guard.alive = false;
-            *velocity = Velocity(Vec2::ZERO);
-            commands.entity(entity).remove::<Collider>();
-            commands.entity(entity).insert(FadeOut {
-                timer: Timer::from_seconds(0.3, TimerMode::Once),
-            });
+        if !aabb_intersects(sword_pos, sword_size, guard_pos, collider.size) {
+            continue;
+        }
+
+        let facing_player = guard.direction == -dir;
+        if guard.parry_timer > 0.0 && facing_player {
+            guard.parry_timer = 0.0;
+            return SwordHit::Parried;
+        }
+
+        result = SwordHit::Hit;
+        let amount = if guard.state.is_aware() && facing_player || !guard.backstab {
+            damage
+        } else {
+            health.current
+        };
+        let kind = damage_guard(
+            commands,
+            entity,
+            &mut guard,
+            &mut velocity,
+            &mut health,
+            amount,
+            dir,
+        );
+        impacts.write(ImpactEvent {
+            kind,
+            pos: guard_pos,
+            dir,
+            target: Some(entity),
+        });
+    }
+    result
+}
+
+fn damage_guard(
+    commands: &mut Commands,
+    entity: Entity,
+    guard: &mut Guard,
+    velocity: &mut Velocity,
+    health: &mut Health,
+    amount: u32,
+    dir: f32,
+) -> ImpactKind {
+    health.current = health.current.saturating_sub(amount);
+    guard.windup_timer = 0.0;
+    guard.stagger_timer = 0.3;
+    velocity.x = dir * guard.knockback;
+
+    if health.current > 0 {
+        if !guard.state.is_aware() {
+            guard.state = GuardState::Alert;
+            guard.state_timer = GUARD_ALERT_TIME;
+            guard.awareness = 1.0;
+            guard.direction = -dir;
         }
+        ImpactKind::Hit
+    } else if let Some(revive_time) = guard.revive_time {
+        guard.downed_timer = revive_time;
+        guard.state = GuardState::Patrol;
+        guard.awareness = 0.0;
+        ImpactKind::Kill
+    } else {
+        kill_guard(commands, entity, guard, velocity);
+        ImpactKind::Kill
     }
 }
 
+fn kill_guard(commands: &mut Commands, entity: Entity, guard: &mut Guard, velocity: &mut Velocity) {
+    guard.alive = false;
+    commands.write_message(GuardDefeatedEvent);
+    **velocity = Vec2::ZERO;
+    commands.entity(entity).remove::<Collider>();
+    commands.entity(entity).insert(FadeOut {
+        timer: Timer::from_seconds(0.3, TimerMode::Once),
+    });
+}
+
+fn next_random(seed: &mut u32) -> f32 {
+    *seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+    (*seed >> 8) as f32 / (1u32 << 24) as f32
+}
+
 fn play_sfx(commands: &mut Commands, audio: Handle<AudioSource>, volume: f32) {
-    commands.spawn((
-        AudioPlayer::new(audio),
-        PlaybackSettings {
-            volume: Volume::Linear(volume),
-            ..PlaybackSettings::DESPAWN
-        },
-    ));
+    commands.queue(move |world: &mut World| {
+        let sfx_volume = world.resource::<Settings>().sfx_volume;
+        world.spawn((
+            AudioPlayer::new(audio),
+            PlaybackSettings {
+                volume: Volume::Linear(volume * sfx_volume),
+                ..PlaybackSettings::DESPAWN
+            },
+        ));
+    });
 }
 
-fn move_input(keys: &ButtonInput<KeyCode>) -> f32 {
-    let mut dir = 0.0;
-    if keys.pressed(KeyCode::ArrowLeft) || keys.pressed(KeyCode::KeyA) {
-        dir -= 1.0;
-    }
-    if keys.pressed(KeyCode::ArrowRight) || keys.pressed(KeyCode::KeyD) {
-        dir += 1.0;
-    }
-    dir
+fn move_input(actions: &ActionInput) -> f32 {
+    actions.axis(Action::MoveLeft, Action::MoveRight)
 }
 
-fn climb_input(keys: &ButtonInput<KeyCode>) -> f32 {
-    let mut dir = 0.0;
-    if keys.pressed(KeyCode::ArrowUp) || keys.pressed(KeyCode::KeyW) {
-        dir += 1.0;
-    }
-    if keys.pressed(KeyCode::ArrowDown) || keys.pressed(KeyCode::KeyS) {
-        dir -= 1.0;
-    }
-    dir
+fn climb_input(actions: &ActionInput) -> f32 {
+    actions.axis(Action::Down, Action::Up)
 }
 
 fn aabb_intersects(pos_a: Vec2, size_a: Vec2, pos_b: Vec2, size_b: Vec2) -> bool {
@@ -1923,11 +7584,161 @@ fn aabb_intersects(pos_a: Vec2, size_a: Vec2, pos_b: Vec2, size_b: Vec2) -> bool
         && (pos_a.y - pos_b.y).abs() < (half_a.y + half_b.y)
 }
 
+fn ladder_at<F: QueryFilter>(
+    pos: Vec2,
+    size: Vec2,
+    ladders: &Query<(&Transform, &Collider), F>,
+) -> Option<(Vec2, Vec2)> {
+    let half = size * 0.5;
+    ladders.iter().find_map(|(ladder_tf, ladder_collider)| {
+        let ladder_pos = Vec2::new(ladder_tf.translation.x, ladder_tf.translation.y);
+        let ladder_half = ladder_collider.size * 0.5;
+        let within_x = (pos.x - ladder_pos.x).abs() < ladder_half.x;
+        let within_y = pos.y - half.y <= ladder_pos.y + ladder_half.y + 1.0
+            && pos.y + half.y > ladder_pos.y - ladder_half.y;
+        (within_x && within_y).then_some((ladder_pos, ladder_collider.size))
+    })
+}
+
+fn land_on_ladder_top<F: QueryFilter>(
+    pos: &mut Vec2,
+    prev_y: f32,
+    size: Vec2,
+    ladders: &Query<(&Transform, &Collider), F>,
+) -> bool {
+    let half = size * 0.5;
+    for (ladder_tf, ladder_collider) in ladders.iter() {
+        let ladder_pos = Vec2::new(ladder_tf.translation.x, ladder_tf.translation.y);
+        let ladder_half = ladder_collider.size * 0.5;
+        let top = ladder_pos.y + ladder_half.y;
+        if (pos.x - ladder_pos.x).abs() < half.x + ladder_half.x
+            && prev_y - half.y >= top - 0.01
+            && pos.y - half.y < top
+        {
+            pos.y = top + half.y;
+            return true;
+        }
+    }
+    false
+}
+
+fn ladder_grab(pos: Vec2, size: Vec2, ladder: (Vec2, Vec2), climb_dir: f32, on_ground: bool) -> bool {
+    let (ladder_pos, ladder_size) = ladder;
+    let feet = pos.y - size.y * 0.5;
+    let ladder_top = ladder_pos.y + ladder_size.y * 0.5;
+    let on_top = (feet - ladder_top).abs() < 1.0;
+    let grab_up = climb_dir > 0.0 && feet < ladder_top - 1.0;
+    let grab_down = climb_dir < 0.0 && (on_top || !on_ground);
+    grab_up || grab_down
+}
+
+fn ladder_climbed_out<F: QueryFilter>(
+    pos: &mut Vec2,
+    size: Vec2,
+    facing: f32,
+    ladder: (Vec2, Vec2),
+    solids: &Query<(&Transform, &Collider), F>,
+) -> bool {
+    let (ladder_pos, ladder_size) = ladder;
+    if pos.y - size.y * 0.5 < ladder_pos.y + ladder_size.y * 0.5 {
+        return false;
+    }
+    ladder_top_out(pos, size, facing, ladder_pos, ladder_size, solids);
+    true
+}
+
+fn ladder_toward<F: QueryFilter>(
+    pos: Vec2,
+    size: Vec2,
+    rise: f32,
+    ladders: &Query<(&Transform, &Collider), F>,
+) -> Option<f32> {
+    if rise.abs() < DUEL_FLOOR_TOLERANCE {
+        return None;
+    }
+    let feet = pos.y - size.y * 0.5;
+    ladders
+        .iter()
+        .filter_map(|(ladder_tf, ladder_collider)| {
+            let ladder_half = ladder_collider.size * 0.5;
+            let top = ladder_tf.translation.y + ladder_half.y;
+            let bottom = ladder_tf.translation.y - ladder_half.y;
+            let reachable = feet > bottom - 4.0 && feet < top + 2.0;
+            let leads = if rise > 0.0 { top > feet + 8.0 } else { bottom < feet - 8.0 };
+            (reachable && leads).then_some(ladder_tf.translation.x)
+        })
+        .min_by(|a, b| (a - pos.x).abs().total_cmp(&(b - pos.x).abs()))
+}
+
+fn ladder_top_out<F: QueryFilter>(
+    pos: &mut Vec2,
+    size: Vec2,
+    facing: f32,
+    ladder_pos: Vec2,
+    ladder_size: Vec2,
+    solids: &Query<(&Transform, &Collider), F>,
+) {
+    let half = size * 0.5;
+    let ladder_half = ladder_size * 0.5;
+    let ladder_top = ladder_pos.y + ladder_half.y;
+    let mut best: Option<(bool, f32, Vec2)> = None;
+    for (solid_tf, solid_collider) in solids.iter() {
+        let solid_pos = Vec2::new(solid_tf.translation.x, solid_tf.translation.y);
+        let solid_half = solid_collider.size * 0.5;
+        let top = solid_pos.y + solid_half.y;
+        if (top - ladder_top).abs() > 10.0 {
+            continue;
+        }
+        let (side, gap, x) = if solid_pos.x > ladder_pos.x {
+            let edge = solid_pos.x - solid_half.x;
+            (1.0, edge - (ladder_pos.x + ladder_half.x), edge + half.x + 1.0)
+        } else {
+            let edge = solid_pos.x + solid_half.x;
+            (-1.0, (ladder_pos.x - ladder_half.x) - edge, edge - half.x - 1.0)
+        };
+        if gap > 12.0 {
+            continue;
+        }
+        let candidate = (side != facing, gap, Vec2::new(x, top + half.y));
+        let better = match best {
+            Some((off_facing, best_gap, _)) => (candidate.0, candidate.1) < (off_facing, best_gap),
+            None => true,
+        };
+        if better {
+            best = Some(candidate);
+        }
+    }
+
+    match best {
+        Some((_, _, target)) => *pos = target,
+        None => pos.y = ladder_top + half.y,
+    }
+}
+
 fn move_with_collisions<F: QueryFilter>(
     pos: &mut Vec2,
     delta: Vec2,
     size: Vec2,
     solids: &Query<(&Transform, &Collider), F>,
+) -> (bool, bool) {
+    move_against(
+        pos,
+        delta,
+        size,
+        solids.iter().map(|(solid_tf, solid_collider)| {
+            (
+                Vec2::new(solid_tf.translation.x, solid_tf.translation.y),
+                solid_collider.size,
+            )
+        }),
+    )
+}
+
+fn move_against<I: Iterator<Item = (Vec2, Vec2)> + Clone>(
+    pos: &mut Vec2,
+    delta: Vec2,
+    size: Vec2,
+    solids: I,
 ) -> (bool, bool) {
     let mut hit_x = false;
     let mut hit_y = false;
@@ -1935,9 +7746,8 @@ fn move_with_collisions<F: QueryFilter>(
     if delta.x != 0.0 {
         pos.x += delta.x;
         let half = size * 0.5;
-        for (solid_tf, solid_collider) in solids.iter() {
-            let other_pos = Vec2::new(solid_tf.translation.x, solid_tf.translation.y);
-            let other_half = solid_collider.size * 0.5;
+        for (other_pos, other_size) in solids.clone() {
+            let other_half = other_size * 0.5;
             if (pos.y - other_pos.y).abs() < (half.y + other_half.y) {
                 let min_x = pos.x - half.x;
                 let max_x = pos.x + half.x;
@@ -1958,9 +7768,8 @@ fn move_with_collisions<F: QueryFilter>(
     if delta.y != 0.0 {
         pos.y += delta.y;
         let half = size * 0.5;
-        for (solid_tf, solid_collider) in solids.iter() {
-            let other_pos = Vec2::new(solid_tf.translation.x, solid_tf.translation.y);
-            let other_half = solid_collider.size * 0.5;
+        for (other_pos, other_size) in solids {
+            let other_half = other_size * 0.5;
             if (pos.x - other_pos.x).abs() < (half.x + other_half.x) {
                 let min_y = pos.y - half.y;
                 let max_y = pos.y + half.y;
@@ -1988,3 +7797,134 @@ fn to_world(pos: Vec2) -> Vec2 {
 fn db_to_linear(db: f32) -> f32 {
     10.0_f32.powf(db / 20.0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::input::gamepad::{
+        GamepadConnection, RawGamepadAxisChangedEvent, RawGamepadButtonChangedEvent,
+        RawGamepadEvent,
+    };
+    use bevy::input::keyboard::{Key, KeyboardInput};
+    use bevy::input::{ButtonState, InputPlugin};
+
+    fn input_app() -> (App, Entity) {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, InputPlugin))
+            .insert_resource(Settings::default())
+            .init_resource::<ActionInput>()
+            .add_systems(PreUpdate, action_input_system.after(InputSystems))
+            .add_systems(Update, gamepad_connection_system);
+        let gamepad = app.world_mut().spawn_empty().id();
+        app.world_mut().write_message(GamepadConnectionEvent::new(
+            gamepad,
+            GamepadConnection::Connected {
+                name: "Test pad".into(),
+                vendor_id: None,
+                product_id: None,
+            },
+        ));
+        app.update();
+        (app, gamepad)
+    }
+
+    fn button(app: &mut App, gamepad: Entity, button: GamepadButton, value: f32) {
+        app.world_mut()
+            .write_message(RawGamepadEvent::Button(RawGamepadButtonChangedEvent::new(
+                gamepad, button, value,
+            )));
+        app.update();
+    }
+
+    fn axis(app: &mut App, gamepad: Entity, axis: GamepadAxis, value: f32) {
+        app.world_mut()
+            .write_message(RawGamepadEvent::Axis(RawGamepadAxisChangedEvent::new(
+                gamepad, axis, value,
+            )));
+        app.update();
+    }
+
+    fn actions(app: &App) -> &ActionInput {
+        app.world().resource::<ActionInput>()
+    }
+
+    #[test]
+    fn gamepad_buttons_drive_actions() {
+        let (mut app, gamepad) = input_app();
+        button(&mut app, gamepad, GamepadButton::South, 1.0);
+        assert!(actions(&app).pressed(Action::Jump));
+        assert!(actions(&app).just_pressed(Action::Jump));
+        assert!(actions(&app).just_pressed(Action::Confirm));
+        assert_eq!(actions(&app).device, InputDevice::Gamepad);
+
+        app.update();
+        assert!(actions(&app).pressed(Action::Jump));
+        assert!(!actions(&app).just_pressed(Action::Jump));
+
+        button(&mut app, gamepad, GamepadButton::South, 0.0);
+        assert!(!actions(&app).pressed(Action::Jump));
+    }
+
+    #[test]
+    fn dpad_maps_to_movement() {
+        let (mut app, gamepad) = input_app();
+        button(&mut app, gamepad, GamepadButton::DPadLeft, 1.0);
+        assert!(actions(&app).pressed(Action::MoveLeft));
+        button(&mut app, gamepad, GamepadButton::DPadLeft, 0.0);
+        button(&mut app, gamepad, GamepadButton::DPadUp, 1.0);
+        assert!(!actions(&app).pressed(Action::MoveLeft));
+        assert!(actions(&app).just_pressed(Action::Up));
+    }
+
+    #[test]
+    fn left_stick_respects_deadzone() {
+        let (mut app, gamepad) = input_app();
+        axis(&mut app, gamepad, GamepadAxis::LeftStickX, 0.2);
+        assert!(!actions(&app).pressed(Action::MoveRight));
+        assert_eq!(actions(&app).device, InputDevice::Keyboard);
+
+        axis(&mut app, gamepad, GamepadAxis::LeftStickX, 0.8);
+        assert!(actions(&app).pressed(Action::MoveRight));
+        assert_eq!(actions(&app).device, InputDevice::Gamepad);
+
+        axis(&mut app, gamepad, GamepadAxis::LeftStickX, 0.0);
+        axis(&mut app, gamepad, GamepadAxis::LeftStickY, -0.8);
+        assert!(!actions(&app).pressed(Action::MoveRight));
+        assert!(actions(&app).pressed(Action::Down));
+    }
+
+    #[test]
+    fn device_follows_last_input() {
+        let (mut app, gamepad) = input_app();
+        button(&mut app, gamepad, GamepadButton::West, 1.0);
+        assert_eq!(actions(&app).device, InputDevice::Gamepad);
+        button(&mut app, gamepad, GamepadButton::West, 0.0);
+
+        let window = app.world_mut().spawn_empty().id();
+        app.world_mut().write_message(KeyboardInput {
+            key_code: KeyCode::KeyE,
+            logical_key: Key::Character("e".into()),
+            state: ButtonState::Pressed,
+            text: None,
+            repeat: false,
+            window,
+        });
+        app.update();
+        assert_eq!(actions(&app).device, InputDevice::Keyboard);
+        assert!(actions(&app).pressed(Action::Attack));
+    }
+
+    #[test]
+    fn disconnect_falls_back_to_keyboard() {
+        let (mut app, gamepad) = input_app();
+        button(&mut app, gamepad, GamepadButton::South, 1.0);
+        assert_eq!(actions(&app).device, InputDevice::Gamepad);
+
+        app.world_mut().write_message(GamepadConnectionEvent::new(
+            gamepad,
+            GamepadConnection::Disconnected,
+        ));
+        app.update();
+        assert_eq!(actions(&app).device, InputDevice::Keyboard);
+    }
+}